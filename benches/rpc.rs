@@ -0,0 +1,299 @@
+//! End-to-end latency and throughput of `HandleRequest`/`HandleResponse`.
+//!
+//! A pass-through plugin is started with `serve()` on a unix socket and on TCP, and called
+//! over gRPC the way mcpd calls it: with bodies from 1 KiB to 10 MiB, and with up to 64
+//! calls in flight.
+//!
+//! ```bash
+//! cargo bench --bench rpc
+//! cargo bench --bench rpc -- handle_response/unix
+//! ```
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mcpd_plugins_sdk::{
+    serve, Capabilities, HttpRequest, HttpResponse, Plugin, FLOW_REQUEST, FLOW_RESPONSE,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+use tonic::client::Grpc;
+use tonic::codec::ProstCodec;
+use tonic::transport::{Channel, Endpoint};
+use tonic::Request;
+
+/// Body sizes of the latency benchmarks.
+const BODY_SIZES: &[usize] = &[1 << 10, 64 << 10, 1 << 20, 10 << 20];
+
+/// Calls in flight in the concurrency benchmarks.
+const CONCURRENCY: &[usize] = &[1, 8, 64];
+
+/// Body size of the concurrency benchmarks.
+const CONCURRENT_BODY_SIZE: usize = 16 << 10;
+
+/// Message size limit of the server and client in both directions, above tonic's 4 MiB
+/// decoding default to fit the largest body.
+const MAX_MESSAGE_SIZE: usize = 16 << 20;
+
+/// Room left in a message for the fields around the body.
+const MESSAGE_OVERHEAD: usize = 64 << 10;
+
+// Every body size is benchmarked; a size the limits cannot carry fails to compile rather
+// than being skipped.
+const _: () = {
+    let mut i = 0;
+    while i < BODY_SIZES.len() {
+        assert!(
+            BODY_SIZES[i] + MESSAGE_OVERHEAD <= MAX_MESSAGE_SIZE,
+            "a benchmarked body size does not fit in MAX_MESSAGE_SIZE"
+        );
+        i += 1;
+    }
+};
+
+const HANDLE_REQUEST: &str = "/mozilla.mcpd.plugins.v1.Plugin/HandleRequest";
+const HANDLE_RESPONSE: &str = "/mozilla.mcpd.plugins.v1.Plugin/HandleResponse";
+
+/// Plugin that declares both flows and passes everything through unchanged.
+struct PassThrough;
+
+#[tonic::async_trait]
+impl Plugin for PassThrough {
+    async fn get_capabilities(
+        &self,
+        _request: Request<()>,
+    ) -> std::result::Result<tonic::Response<Capabilities>, tonic::Status> {
+        Ok(tonic::Response::new(Capabilities {
+            flows: vec![FLOW_REQUEST as i32, FLOW_RESPONSE as i32],
+        }))
+    }
+}
+
+/// A plugin served on one transport, and a client connected to it.
+struct Target {
+    name: &'static str,
+    client: Grpc<Channel>,
+    socket: Option<PathBuf>,
+}
+
+impl Target {
+    /// Serves the plugin on a unix socket in the temporary directory.
+    #[cfg(unix)]
+    fn unix(runtime: &Runtime) -> Self {
+        let path = std::env::temp_dir().join(format!("mcpd-bench-{}.sock", std::process::id()));
+        let address = path.to_string_lossy().into_owned();
+        runtime.spawn(serve(PassThrough, Some(args("unix", &address))));
+
+        let client = runtime.block_on(retry(|| mcpd_plugins_sdk::connect_unix(&address)));
+        Self {
+            name: "unix",
+            client: Grpc::new(client)
+                .max_decoding_message_size(MAX_MESSAGE_SIZE)
+                .max_encoding_message_size(MAX_MESSAGE_SIZE),
+            socket: Some(path),
+        }
+    }
+
+    /// Serves the plugin on a free TCP port on the loopback interface.
+    fn tcp(runtime: &Runtime) -> Self {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("no free TCP port")
+            .port();
+        let address = format!("127.0.0.1:{}", port);
+        runtime.spawn(serve(PassThrough, Some(args("tcp", &address))));
+
+        let endpoint = Endpoint::from_shared(format!("http://{}", address)).unwrap();
+        let client = runtime.block_on(retry(|| async {
+            endpoint
+                .connect()
+                .await
+                .map_err(mcpd_plugins_sdk::PluginError::from)
+        }));
+        Self {
+            name: "tcp",
+            client: Grpc::new(client)
+                .max_decoding_message_size(MAX_MESSAGE_SIZE)
+                .max_encoding_message_size(MAX_MESSAGE_SIZE),
+            socket: None,
+        }
+    }
+
+    async fn handle_request(&self, request: HttpRequest) -> HttpResponse {
+        call(self.client.clone(), HANDLE_REQUEST, request).await
+    }
+
+    async fn handle_response(&self, response: HttpResponse) -> HttpResponse {
+        call(self.client.clone(), HANDLE_RESPONSE, response).await
+    }
+}
+
+impl Drop for Target {
+    /// Removes the socket file, which the server only removes on a graceful shutdown.
+    fn drop(&mut self) {
+        if let Some(path) = &self.socket {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn args(network: &str, address: &str) -> Vec<String> {
+    let limit = MAX_MESSAGE_SIZE.to_string();
+    [
+        "bench",
+        "--network",
+        network,
+        "--address",
+        address,
+        "--max-decoding-message-size",
+        &limit,
+        "--max-encoding-message-size",
+        &limit,
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+/// Retries `connect` until the server is listening.
+async fn retry<F, Fut>(connect: F) -> Channel
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = mcpd_plugins_sdk::Result<Channel>>,
+{
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        match connect().await {
+            Ok(channel) => return channel,
+            Err(err) if Instant::now() > deadline => panic!("plugin did not start: {}", err),
+            Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+        }
+    }
+}
+
+/// Makes a unary call, panicking on failure since a failing call would skew the results.
+async fn call<M>(mut client: Grpc<Channel>, path: &'static str, message: M) -> HttpResponse
+where
+    M: prost::Message + Send + Sync + 'static,
+{
+    client.ready().await.expect("plugin is not ready");
+    client
+        .unary(
+            Request::new(message),
+            http::uri::PathAndQuery::from_static(path),
+            ProstCodec::default(),
+        )
+        .await
+        .unwrap_or_else(|status| panic!("{} failed: {}", path, status))
+        .into_inner()
+}
+
+fn http_request(size: usize) -> HttpRequest {
+    HttpRequest {
+        method: "POST".to_string(),
+        url: "http://localhost:8090/api/v1/servers/time/tools/get_current_time".to_string(),
+        path: "/api/v1/servers/time/tools/get_current_time".to_string(),
+        headers: headers(),
+        body: vec![b'x'; size].into(),
+        remote_addr: "127.0.0.1:54321".to_string(),
+        request_uri: "/api/v1/servers/time/tools/get_current_time".to_string(),
+    }
+}
+
+fn http_response(size: usize) -> HttpResponse {
+    HttpResponse {
+        status_code: 200,
+        headers: headers(),
+        body: vec![b'x'; size].into(),
+        ..Default::default()
+    }
+}
+
+fn headers() -> HashMap<String, String> {
+    [
+        ("Content-Type", "application/json"),
+        ("Authorization", "Bearer bench"),
+        ("User-Agent", "mcpd-bench"),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value.to_string()))
+    .collect()
+}
+
+fn targets(runtime: &Runtime) -> Vec<Target> {
+    vec![
+        #[cfg(unix)]
+        Target::unix(runtime),
+        Target::tcp(runtime),
+    ]
+}
+
+fn latency(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let targets = targets(&runtime);
+
+    let mut group = c.benchmark_group("handle_request");
+    for target in &targets {
+        for &size in BODY_SIZES {
+            let request = http_request(size);
+            group.throughput(Throughput::Bytes(size as u64));
+            group.bench_with_input(
+                BenchmarkId::new(target.name, size),
+                &request,
+                |b, request| {
+                    b.to_async(&runtime)
+                        .iter(|| target.handle_request(request.clone()))
+                },
+            );
+        }
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("handle_response");
+    for target in &targets {
+        for &size in BODY_SIZES {
+            let response = http_response(size);
+            group.throughput(Throughput::Bytes(size as u64));
+            group.bench_with_input(
+                BenchmarkId::new(target.name, size),
+                &response,
+                |b, response| {
+                    b.to_async(&runtime)
+                        .iter(|| target.handle_response(response.clone()))
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn concurrency(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let targets = targets(&runtime);
+    let request = http_request(CONCURRENT_BODY_SIZE);
+
+    let mut group = c.benchmark_group("concurrent_handle_request");
+    for target in &targets {
+        for &calls in CONCURRENCY {
+            group.throughput(Throughput::Elements(calls as u64));
+            group.bench_with_input(BenchmarkId::new(target.name, calls), &calls, |b, &calls| {
+                b.to_async(&runtime).iter(|| async {
+                    let tasks: Vec<_> = (0..calls)
+                        .map(|_| {
+                            let client = target.client.clone();
+                            let request = request.clone();
+                            tokio::spawn(call(client, HANDLE_REQUEST, request))
+                        })
+                        .collect();
+                    for task in tasks {
+                        task.await.unwrap();
+                    }
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, latency, concurrency);
+criterion_main!(benches);
@@ -1,56 +1,298 @@
 use std::env;
-use std::path::PathBuf;
+use std::error::Error;
+use std::path::{Path, PathBuf};
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Get the proto version from environment or use default.
-    let proto_version = env::var("PROTO_VERSION").unwrap_or_else(|_| "v1".to_string());
+#[path = "build/descriptor.rs"]
+mod descriptor;
 
-    println!("cargo:rerun-if-changed=proto/plugin.proto");
+/// Protocol versions vendored under `proto/<version>/plugin.proto`, with the SHA-256 of
+/// each file as published in mcpd-proto. Each has a `proto-<version>` feature.
+const VENDORED_PROTOS: &[(&str, &str)] = &[(
+    "v1",
+    "bafdb1274be8cad9ff6ff90e1ead4e75f8c9490d265d6239bdc58151160144da",
+)];
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("cargo:rerun-if-changed=proto");
     println!("cargo:rerun-if-env-changed=PROTO_VERSION");
+    println!("cargo:rerun-if-env-changed=PROTO_PATH");
+    println!("cargo:rerun-if-env-changed=PROTO_REF");
     println!("cargo:rerun-if-env-changed=FORCE_CODEGEN");
+    println!("cargo:rerun-if-env-changed=USE_PROTOC");
 
-    let proto_path = PathBuf::from("proto/plugin.proto");
-    let generated_file = PathBuf::from("src/generated/mozilla.mcpd.plugins.v1.rs");
+    // Generate each protocol version enabled by its `proto-<version>` feature, and v1,
+    // whose types the `Plugin` trait uses, or only PROTO_VERSION when it is set (e.g. to
+    // fetch and generate a new version).
+    let versions: Vec<String> = match env::var("PROTO_VERSION") {
+        Ok(version) => vec![version],
+        Err(_) => VENDORED_PROTOS
+            .iter()
+            .map(|(version, _)| version.to_string())
+            .filter(|version| {
+                version == "v1"
+                    || env::var(format!("CARGO_FEATURE_PROTO_{}", version.to_uppercase())).is_ok()
+            })
+            .collect(),
+    };
 
-    // Check if we need to regenerate code.
-    // Skip generation if both proto and generated files exist, unless FORCE_CODEGEN is set.
-    let force_codegen = env::var("FORCE_CODEGEN").is_ok();
-    let needs_generation = force_codegen || !proto_path.exists() || !generated_file.exists();
+    if env::var("PROTO_PATH").is_ok() && versions.len() > 1 {
+        return Err(
+            "PROTO_PATH is ambiguous with several protocol versions enabled; \
+                    set PROTO_VERSION to the version it provides"
+                .into(),
+        );
+    }
 
-    if !needs_generation {
-        eprintln!("Using existing generated code (set FORCE_CODEGEN=1 to regenerate)");
-        return Ok(());
+    for version in &versions {
+        generate(version)?;
     }
 
-    // Download proto file if it doesn't exist.
-    if !proto_path.exists() {
-        eprintln!("Downloading plugin.proto version {}...", proto_version);
-        std::fs::create_dir_all("proto")?;
+    Ok(())
+}
 
-        let url = format!(
-            "https://raw.githubusercontent.com/mozilla-ai/mcpd-proto/refs/heads/main/plugins/{}/plugin.proto",
-            proto_version
-        );
+/// Generates the Rust code and descriptor set for protocol `version`, if needed.
+fn generate(version: &str) -> Result<(), Box<dyn Error>> {
+    let generated_file =
+        PathBuf::from(format!("src/generated/mozilla.mcpd.plugins.{}.rs", version));
+    let descriptor_file = PathBuf::from(format!(
+        "src/generated/mozilla.mcpd.plugins.{}.bin",
+        version
+    ));
+    let serde_file = PathBuf::from(format!(
+        "src/generated/mozilla.mcpd.plugins.{}.serde.rs",
+        version
+    ));
 
-        // Use a simple HTTP client to download the file.
-        let response = ureq::get(&url).call()?;
-        let mut file = std::fs::File::create(&proto_path)?;
-        std::io::copy(&mut response.into_reader(), &mut file)?;
+    // Check if we need to regenerate code.
+    // Skip generation if the generated files exist, unless FORCE_CODEGEN is set.
+    // The reflection feature also needs the encoded file descriptor set, and the serde
+    // feature the generated serde impls.
+    let force_codegen = env::var("FORCE_CODEGEN").is_ok();
+    let needs_descriptor =
+        env::var("CARGO_FEATURE_REFLECTION").is_ok() && !descriptor_file.exists();
+    let needs_serde = env::var("CARGO_FEATURE_SERDE").is_ok() && !serde_file.exists();
+    let needs_generation =
+        force_codegen || needs_descriptor || needs_serde || !generated_file.exists();
 
-        eprintln!("Downloaded proto file successfully");
+    if !needs_generation {
+        // Still catch edits to a vendored proto that the generated code doesn't reflect.
+        let vendored = vendored_path(version);
+        if vendored.exists() {
+            verify_vendored(version, &vendored)?;
+        }
+        eprintln!(
+            "Using existing generated code for {} (set FORCE_CODEGEN=1 to regenerate)",
+            version
+        );
+        return Ok(());
     }
 
+    let proto_path = resolve_proto(version)?;
+    let include_dir = proto_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
     // Ensure output directory exists.
     let out_dir = PathBuf::from("src/generated");
     std::fs::create_dir_all(&out_dir)?;
 
     // Configure protobuf compilation.
-    eprintln!("Generating Rust code from protobuf...");
-    tonic_build::configure()
+    eprintln!("Generating Rust code from {}...", proto_path.display());
+    // Bodies are `bytes::Bytes`, so passing one through or cloning a message never copies it.
+    let package = format!(".mozilla.mcpd.plugins.{}", version);
+    let config = tonic_build::configure()
         .build_server(true)
         .build_client(false)
-        .out_dir(&out_dir)
-        .compile_protos(&["proto/plugin.proto"], &["proto"])?;
+        .bytes([
+            format!("{}.HTTPRequest.body", package),
+            format!("{}.HTTPResponse.body", package),
+        ])
+        .out_dir(&out_dir);
+
+    // Parse with protobuf-parse, or with a system protoc when USE_PROTOC=1 is set. Only
+    // protoc keeps the proto's comments as doc comments; the committed code is built with it.
+    if env::var("USE_PROTOC").is_ok() {
+        config
+            .file_descriptor_set_path(&descriptor_file)
+            .compile_protos(&[&proto_path], &[include_dir])?;
+    } else {
+        let file_name = proto_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or("proto path has no file name")?;
+        let descriptor_set = descriptor::parse(file_name, include_dir)?;
+        std::fs::write(
+            &descriptor_file,
+            prost::Message::encode_to_vec(&descriptor_set),
+        )?;
+        config.compile_fds(descriptor_set)?;
+    }
+
+    generate_serde(version, &descriptor_file, &out_dir)?;
+
+    Ok(())
+}
+
+/// Generates serde impls following the protobuf JSON mapping, with the `serde` feature.
+#[cfg(feature = "serde")]
+fn generate_serde(
+    version: &str,
+    descriptor_file: &Path,
+    out_dir: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let descriptor_set = std::fs::read(descriptor_file)?;
+    pbjson_build::Builder::new()
+        .register_descriptors(&descriptor_set)?
+        .out_dir(out_dir)
+        .build(&[format!(".mozilla.mcpd.plugins.{}", version)])?;
+    Ok(())
+}
 
+#[cfg(not(feature = "serde"))]
+fn generate_serde(
+    _version: &str,
+    _descriptor_file: &Path,
+    _out_dir: &Path,
+) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
+
+/// Returns the path of the vendored proto for `version`.
+fn vendored_path(version: &str) -> PathBuf {
+    PathBuf::from(format!("proto/{}/plugin.proto", version))
+}
+
+/// Locates the proto to compile for `version`.
+///
+/// `PROTO_PATH` takes precedence and is used as is. Otherwise the vendored proto is used,
+/// after checking it against its pinned checksum. Downloading is only attempted with the
+/// `download-proto` feature.
+fn resolve_proto(version: &str) -> Result<PathBuf, Box<dyn Error>> {
+    if let Ok(path) = env::var("PROTO_PATH") {
+        let path = PathBuf::from(path);
+        if !path.exists() {
+            return Err(format!(
+                "PROTO_PATH points to {}, which does not exist",
+                path.display()
+            )
+            .into());
+        }
+        return Ok(path);
+    }
+
+    let path = vendored_path(version);
+    if !path.exists() {
+        download(version, &path)?;
+    }
+    verify_vendored(version, &path)?;
+    Ok(path)
+}
+
+/// Checks a vendored proto against the checksum pinned in `VENDORED_PROTOS`.
+fn verify_vendored(version: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+    verify(version, &path.display().to_string(), &std::fs::read(path)?)
+}
+
+/// Checks the contents of the proto for `version`, read from `source`, against the checksum
+/// pinned in `VENDORED_PROTOS`. Protos without a pinned checksum are rejected.
+fn verify(version: &str, source: &str, contents: &[u8]) -> Result<(), Box<dyn Error>> {
+    let actual = sha256_hex(contents);
+    match pinned_checksum(version) {
+        Some(expected) if expected == actual => Ok(()),
+        Some(expected) => Err(format!(
+            "{} does not match its pinned checksum (expected sha256 {}, found {}). \
+             Restore the vendored file, or update VENDORED_PROTOS in build.rs if the protocol \
+             was intentionally updated.",
+            source, expected, actual
+        )
+        .into()),
+        None => Err(format!(
+            "{} has no pinned checksum (found sha256 {}). Once the file is verified against \
+             mcpd-proto, add (\"{}\", \"<sha256>\") to VENDORED_PROTOS in build.rs.",
+            source, actual, version
+        )
+        .into()),
+    }
+}
+
+/// Returns the checksum pinned for `version` in `VENDORED_PROTOS`.
+fn pinned_checksum(version: &str) -> Option<&'static str> {
+    VENDORED_PROTOS
+        .iter()
+        .find(|(v, _)| *v == version)
+        .map(|(_, checksum)| *checksum)
+}
+
+/// Downloads `plugin.proto` for `version` from the mcpd-proto repository at the tag or
+/// commit given by `PROTO_REF`.
+///
+/// The file is read into memory and checked against its pinned checksum before it is
+/// written to `path`, so only a verified proto ever lands in `proto/`.
+#[cfg(feature = "download-proto")]
+fn download(version: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+    use std::io::Read;
+
+    /// Upper bound on the size of a downloaded proto.
+    const MAX_PROTO_SIZE: u64 = 1 << 20;
+
+    if pinned_checksum(version).is_none() {
+        return Err(format!(
+            "cannot download plugin.proto version {} without a pinned checksum; add its sha256 \
+             to VENDORED_PROTOS in build.rs first",
+            version
+        )
+        .into());
+    }
+    let reference = env::var("PROTO_REF").map_err(|_| {
+        "set PROTO_REF to the mcpd-proto tag or commit to download plugin.proto from"
+    })?;
+
+    let url = format!(
+        "https://raw.githubusercontent.com/mozilla-ai/mcpd-proto/{}/plugins/{}/plugin.proto",
+        reference, version
+    );
+    eprintln!("Downloading {}...", url);
+
+    let mut contents = Vec::new();
+    ureq::get(&url)
+        .call()?
+        .into_reader()
+        .take(MAX_PROTO_SIZE)
+        .read_to_end(&mut contents)?;
+    verify(version, &url, &contents)?;
+
+    // Write next to the destination and rename, so an interrupted build leaves no partial file.
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let partial = path.with_extension("proto.partial");
+    std::fs::write(&partial, &contents)?;
+    std::fs::rename(&partial, path)?;
+
+    eprintln!("Downloaded and verified {}", path.display());
+    Ok(())
+}
+
+#[cfg(not(feature = "download-proto"))]
+fn download(version: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+    Err(format!(
+        "plugin.proto for protocol version {version} is not vendored at {path}. \
+         Supply it locally by copying it to {path} or setting PROTO_PATH=/path/to/plugin.proto, \
+         or pin its checksum and enable the `download-proto` feature to fetch it from \
+         https://github.com/mozilla-ai/mcpd-proto at PROTO_REF.",
+        version = version,
+        path = path.display()
+    )
+    .into())
+}
+
+/// Computes the SHA-256 digest of `data` as lowercase hex.
+fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -2,30 +2,96 @@
 //!
 //! This plugin demonstrates how to implement request validation and
 //! short-circuit the processing pipeline by returning early responses.
+//! Paths that skip authentication are declared with a `RequestRouter`
+//! bypass list, configurable via the `bypass_paths` custom config key.
 
 use mcpd_plugins_sdk::{
-    serve, Capabilities, HttpRequest, HttpResponse, Metadata, Plugin, PluginConfig, FLOW_REQUEST,
+    serve, Capabilities, HttpRequest, HttpResponse, Metadata, PathParams, Plugin, PluginConfig,
+    PluginError, RequestRouter, FLOW_REQUEST,
 };
 use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tonic::{Request, Response, Status};
 
-struct AuthPlugin {
-    valid_tokens: Arc<RwLock<HashSet<String>>>,
+/// Paths that skip authentication unless overridden by `bypass_paths`.
+const DEFAULT_BYPASS_PATHS: &str = "/health, /ready";
+
+type Tokens = Arc<RwLock<HashSet<String>>>;
+
+pub(crate) struct AuthPlugin {
+    valid_tokens: Tokens,
+    router: RwLock<RequestRouter>,
 }
 
 impl AuthPlugin {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         let mut tokens = HashSet::new();
         // Add some default tokens for demo purposes.
         tokens.insert("demo-token-123".to_string());
         tokens.insert("test-token-456".to_string());
+        let valid_tokens = Arc::new(RwLock::new(tokens));
+
+        let router = build_router(valid_tokens.clone(), DEFAULT_BYPASS_PATHS)
+            .expect("default bypass paths are valid");
 
         Self {
-            valid_tokens: Arc::new(RwLock::new(tokens)),
+            valid_tokens,
+            router: RwLock::new(router),
+        }
+    }
+}
+
+/// Builds a router that lets `bypass_paths` through and authenticates everything else.
+fn build_router(valid_tokens: Tokens, bypass_paths: &str) -> Result<RequestRouter, PluginError> {
+    Ok(RequestRouter::new().bypass_list(bypass_paths)?.fallback(
+        move |req: HttpRequest, _params: PathParams| authenticate(req, valid_tokens.clone()),
+    ))
+}
+
+/// Continues if the request carries a valid Bearer token, otherwise returns 401.
+async fn authenticate(req: HttpRequest, valid_tokens: Tokens) -> Result<HttpResponse, Status> {
+    // Check for Authorization header.
+    if let Some(auth_header) = req.headers.get("Authorization") {
+        if let Some(token) = auth_header.strip_prefix("Bearer ") {
+            // Validate token.
+            let valid_tokens = valid_tokens.read().await;
+            if valid_tokens.contains(token) {
+                tracing::info!("Valid token provided, allowing request");
+                return Ok(HttpResponse {
+                    r#continue: true,
+                    ..Default::default()
+                });
+            } else {
+                tracing::warn!("Invalid token provided");
+            }
+        } else {
+            tracing::warn!("Authorization header present but not Bearer token");
         }
+    } else {
+        tracing::warn!("No Authorization header provided");
     }
+
+    // Return 401 Unauthorized.
+    let body = serde_json::json!({
+        "error": "unauthorized",
+        "message": "Valid Bearer token required"
+    });
+
+    let mut headers = std::collections::HashMap::new();
+    headers.insert("Content-Type".to_string(), "application/json".to_string());
+    headers.insert(
+        "WWW-Authenticate".to_string(),
+        "Bearer realm=\"mcpd\"".to_string(),
+    );
+
+    Ok(HttpResponse {
+        r#continue: false,
+        status_code: 401,
+        headers,
+        body: body.to_string().into(),
+        ..Default::default()
+    })
 }
 
 #[tonic::async_trait]
@@ -63,6 +129,13 @@ impl Plugin for AuthPlugin {
             tracing::info!("Loaded {} valid tokens from config", valid_tokens.len());
         }
 
+        // Parse paths that skip authentication.
+        if let Some(bypass_paths) = config.custom_config.get("bypass_paths") {
+            let router = build_router(self.valid_tokens.clone(), bypass_paths)?;
+            *self.router.write().await = router;
+            tracing::info!("Bypassing authentication for: {}", bypass_paths);
+        }
+
         Ok(Response::new(()))
     }
 
@@ -70,66 +143,18 @@ impl Plugin for AuthPlugin {
         &self,
         request: Request<HttpRequest>,
     ) -> Result<Response<HttpResponse>, Status> {
-        let req = request.into_inner();
-
+        let req = request.get_ref();
         tracing::info!("Authenticating {} request to {}", req.method, req.path);
 
-        // Skip health check endpoints.
-        if req.path == "/health" || req.path == "/ready" {
-            return Ok(Response::new(HttpResponse {
-                r#continue: true,
-                ..Default::default()
-            }));
-        }
-
-        // Check for Authorization header.
-        if let Some(auth_header) = req.headers.get("Authorization") {
-            if let Some(token) = auth_header.strip_prefix("Bearer ") {
-                // Validate token.
-                let valid_tokens = self.valid_tokens.read().await;
-                if valid_tokens.contains(token) {
-                    tracing::info!("Valid token provided, allowing request");
-                    return Ok(Response::new(HttpResponse {
-                        r#continue: true,
-                        ..Default::default()
-                    }));
-                } else {
-                    tracing::warn!("Invalid token provided");
-                }
-            } else {
-                tracing::warn!("Authorization header present but not Bearer token");
-            }
-        } else {
-            tracing::warn!("No Authorization header provided");
-        }
-
-        // Return 401 Unauthorized.
-        let body = serde_json::json!({
-            "error": "unauthorized",
-            "message": "Valid Bearer token required"
-        });
-
-        let mut headers = std::collections::HashMap::new();
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-        headers.insert(
-            "WWW-Authenticate".to_string(),
-            "Bearer realm=\"mcpd\"".to_string(),
-        );
-
-        Ok(Response::new(HttpResponse {
-            r#continue: false,
-            status_code: 401,
-            headers,
-            body: body.to_string().into_bytes(),
-            ..Default::default()
-        }))
+        self.router.read().await.handle(request).await
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing.
+    // Initialize tracing on stderr, keeping stdout free for the handshake line.
     tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
         .with_target(false)
         .with_level(true)
         .init();
@@ -53,14 +53,14 @@ impl TokenBucket {
     }
 }
 
-struct RateLimitPlugin {
+pub(crate) struct RateLimitPlugin {
     buckets: Arc<Mutex<HashMap<String, TokenBucket>>>,
     max_requests: f64,
     window_duration: Duration,
 }
 
 impl RateLimitPlugin {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             buckets: Arc::new(Mutex::new(HashMap::new())),
             max_requests: 10.0,
@@ -181,7 +181,7 @@ impl Plugin for RateLimitPlugin {
                 r#continue: false,
                 status_code: 429,
                 headers,
-                body: body.to_string().into_bytes(),
+                body: body.to_string().into(),
                 ..Default::default()
             }))
         }
@@ -190,8 +190,9 @@ impl Plugin for RateLimitPlugin {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing.
+    // Initialize tracing on stderr, keeping stdout free for the handshake line.
     tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
         .with_target(false)
         .with_level(true)
         .init();
@@ -8,7 +8,7 @@ use mcpd_plugins_sdk::{
 };
 use tonic::{Request, Response, Status};
 
-struct SimplePlugin;
+pub(crate) struct SimplePlugin;
 
 #[tonic::async_trait]
 impl Plugin for SimplePlugin {
@@ -59,8 +59,9 @@ impl Plugin for SimplePlugin {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing.
+    // Initialize tracing on stderr, keeping stdout free for the handshake line.
     tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
         .with_target(false)
         .with_level(true)
         .init();
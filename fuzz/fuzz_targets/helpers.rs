@@ -0,0 +1,81 @@
+//! Feeds arbitrary text to the SDK's string parsing: route patterns, bypass lists, the
+//! `grpc-timeout` header and the protocol version negotiation header.
+//!
+//! The input is split at newlines into a bypass list, a route pattern, a method, a request
+//! path, a `grpc-timeout` value and a protocol versions value.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mcpd_plugins_sdk::proto::plugin_server::Plugin as PluginService;
+use mcpd_plugins_sdk::{
+    HttpRequest, HttpResponse, PathParams, Plugin, PluginAdapter, RequestRouter,
+    PROTOCOL_VERSIONS_HEADER,
+};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tonic::metadata::MetadataValue;
+use tonic::{Request, Status};
+
+/// Plugin with every default.
+struct DefaultPlugin;
+
+#[tonic::async_trait]
+impl Plugin for DefaultPlugin {}
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+    let mut fields = input.split('\n');
+    let mut next = || fields.next().unwrap_or_default();
+    let (list, pattern, method, path, timeout, versions) =
+        (next(), next(), next(), next(), next(), next());
+
+    let request = HttpRequest {
+        method: method.to_string(),
+        path: path.to_string(),
+        ..Default::default()
+    };
+
+    runtime().block_on(async {
+        if let Ok(router) = RequestRouter::new().bypass_list(list) {
+            let _ = router.handle(Request::new(request.clone())).await;
+        }
+        if let Ok(router) = RequestRouter::new().try_route(method, pattern, capture) {
+            let _ = router.handle(Request::new(request.clone())).await;
+        }
+
+        let adapter = PluginAdapter::new(DefaultPlugin).rpc_timeout(Duration::from_secs(1));
+
+        let mut call = Request::new(request);
+        if let Ok(value) = MetadataValue::try_from(timeout) {
+            call.metadata_mut().insert("grpc-timeout", value);
+        }
+        let _ = adapter.handle_request(call).await;
+
+        let mut call = Request::new(());
+        if let Ok(value) = MetadataValue::try_from(versions) {
+            call.metadata_mut().insert(PROTOCOL_VERSIONS_HEADER, value);
+        }
+        let _ = adapter.get_metadata(call).await;
+    });
+});
+
+/// Route handler that reads the captured parameters.
+async fn capture(request: HttpRequest, params: PathParams) -> Result<HttpResponse, Status> {
+    let _ = format!("{:?}", params);
+    Ok(HttpResponse {
+        r#continue: true,
+        modified_request: Some(request),
+        ..Default::default()
+    })
+}
+
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .expect("failed to build runtime")
+    })
+}
@@ -0,0 +1,75 @@
+//! Decodes arbitrary bytes as an `HttpRequest` and runs it through the adapter with the
+//! default plugin, a plugin chain and a request router.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mcpd_plugins_sdk::proto::plugin_server::Plugin as PluginService;
+use mcpd_plugins_sdk::{
+    HttpRequest, HttpResponse, PathParams, Plugin, PluginAdapter, PluginChain, RequestRouter,
+};
+use prost::Message;
+use std::sync::OnceLock;
+use tonic::{Request, Status};
+
+/// Plugin with every default.
+struct DefaultPlugin;
+
+#[tonic::async_trait]
+impl Plugin for DefaultPlugin {}
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(request) = HttpRequest::decode(data) else {
+        return;
+    };
+
+    runtime().block_on(async {
+        let adapter = PluginAdapter::new(DefaultPlugin);
+        let response = adapter
+            .handle_request(Request::new(request.clone()))
+            .await
+            .expect("default plugin rejected a request")
+            .into_inner();
+        assert!(response.r#continue, "default plugin stopped a request");
+
+        let chain = PluginChain::new()
+            .add("first", DefaultPlugin)
+            .add("second", DefaultPlugin);
+        let _ = PluginAdapter::new(chain)
+            .handle_request(Request::new(request.clone()))
+            .await;
+
+        let router = RequestRouter::new()
+            .bypass("GET", "/health")
+            .bypass("*", "/public/**")
+            .route("POST", "/tools/{name}", echo)
+            .route("*", "/servers/{server}/*/{*rest}", echo);
+        let _ = router.handle(Request::new(request)).await;
+    });
+});
+
+/// Route handler that reads every captured parameter.
+async fn echo(mut request: HttpRequest, params: PathParams) -> Result<HttpResponse, Status> {
+    for name in ["name", "server", "rest"] {
+        if let Some(value) = params.get(name) {
+            request
+                .headers
+                .insert(format!("x-{}", name), value.to_string());
+        }
+    }
+    Ok(HttpResponse {
+        r#continue: true,
+        modified_request: Some(request),
+        ..Default::default()
+    })
+}
+
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .expect("failed to build runtime")
+    })
+}
@@ -0,0 +1,53 @@
+//! Decodes arbitrary bytes as an `HttpResponse` and checks that the default plugin passes
+//! it through the adapter with its status, headers and body intact.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mcpd_plugins_sdk::proto::plugin_server::Plugin as PluginService;
+use mcpd_plugins_sdk::{HttpResponse, Plugin, PluginAdapter, PluginChain};
+use prost::Message;
+use std::sync::OnceLock;
+use tonic::Request;
+
+/// Plugin with every default.
+struct DefaultPlugin;
+
+#[tonic::async_trait]
+impl Plugin for DefaultPlugin {}
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(response) = HttpResponse::decode(data) else {
+        return;
+    };
+
+    runtime().block_on(async {
+        let adapter = PluginAdapter::new(DefaultPlugin);
+        let output = adapter
+            .handle_response(Request::new(response.clone()))
+            .await
+            .expect("default plugin rejected a response")
+            .into_inner();
+        assert!(output.r#continue, "default plugin stopped a response");
+        assert_eq!(output.status_code, response.status_code);
+        assert_eq!(output.headers, response.headers);
+        assert_eq!(output.body, response.body);
+
+        let chain = PluginChain::new()
+            .add("first", DefaultPlugin)
+            .add("second", DefaultPlugin);
+        let _ = PluginAdapter::new(chain)
+            .handle_response(Request::new(response))
+            .await;
+    });
+});
+
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .expect("failed to build runtime")
+    })
+}
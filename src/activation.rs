@@ -0,0 +1,304 @@
+use crate::{PluginError, Result};
+use std::os::unix::io::{FromRawFd, OwnedFd, RawFd};
+use tracing::{info, warn};
+
+/// First file descriptor passed by a service manager (`SD_LISTEN_FDS_START`).
+const LISTEN_FDS_START: RawFd = 3;
+
+/// A listening socket inherited from the parent process.
+pub(crate) enum InheritedListener {
+    Unix(tokio::net::UnixListener),
+    Tcp(tokio::net::TcpListener),
+}
+
+/// Returns the inherited listening file descriptor to serve on, if any.
+///
+/// With `--network fd`, `address` names the descriptor either by number or by its
+/// `LISTEN_FDNAMES` entry. For any other network, a socket passed via `LISTEN_FDS` and
+/// `LISTEN_PID` takes precedence over binding `address`.
+pub(crate) fn inherited_fd(network: &str, address: &str) -> Result<Option<RawFd>> {
+    let fds = listen_fds(
+        &ListenEnv::from_env(),
+        std::process::id(),
+        open_file_limit(),
+    )?;
+    select_fd(network, address, &fds)
+}
+
+/// Picks the descriptor to serve on from those passed via `LISTEN_FDS`.
+fn select_fd(network: &str, address: &str, fds: &[(RawFd, String)]) -> Result<Option<RawFd>> {
+    if network == "fd" {
+        if let Ok(fd) = address.parse() {
+            return Ok(Some(fd));
+        }
+        return match fds.iter().find(|(_, name)| name == address) {
+            Some((fd, _)) => Ok(Some(*fd)),
+            None => Err(PluginError::Configuration(format!(
+                "No inherited file descriptor named {:?} (expected a descriptor number or a LISTEN_FDNAMES entry)",
+                address
+            ))),
+        };
+    }
+
+    match fds.split_first() {
+        Some(((fd, _), rest)) => {
+            if !rest.is_empty() {
+                warn!(
+                    "Received {} sockets via LISTEN_FDS, serving on the first one (use --network fd to pick another)",
+                    fds.len()
+                );
+            }
+            info!(
+                "Socket activation detected, serving on inherited fd {} instead of binding {}",
+                fd, address
+            );
+            Ok(Some(*fd))
+        }
+        None => Ok(None),
+    }
+}
+
+/// The variables of the `LISTEN_FDS` protocol.
+#[derive(Debug, Default)]
+struct ListenEnv {
+    pid: Option<String>,
+    fds: Option<String>,
+    names: Option<String>,
+}
+
+impl ListenEnv {
+    fn from_env() -> Self {
+        Self {
+            pid: std::env::var("LISTEN_PID").ok(),
+            fds: std::env::var("LISTEN_FDS").ok(),
+            names: std::env::var("LISTEN_FDNAMES").ok(),
+        }
+    }
+}
+
+/// Returns the descriptors and names passed via the `LISTEN_FDS` protocol.
+///
+/// The variables only apply when `LISTEN_PID` matches `own_pid`, so they are ignored if a
+/// child inherits the environment. Counts whose descriptors could not all be open under
+/// `fd_limit` are rejected.
+fn listen_fds(env: &ListenEnv, own_pid: u32, fd_limit: u64) -> Result<Vec<(RawFd, String)>> {
+    let for_us = env
+        .pid
+        .as_deref()
+        .and_then(|pid| pid.parse::<u32>().ok())
+        .is_some_and(|pid| pid == own_pid);
+    if !for_us {
+        return Ok(Vec::new());
+    }
+
+    let Some(count) = env.fds.as_deref() else {
+        return Ok(Vec::new());
+    };
+    let invalid = || PluginError::Configuration(format!("Invalid LISTEN_FDS value {:?}", count));
+    let count: RawFd = count.parse().map_err(|_| invalid())?;
+    let end = match LISTEN_FDS_START.checked_add(count) {
+        Some(end) if count >= 0 => end,
+        _ => return Err(invalid()),
+    };
+    if end as u64 > fd_limit {
+        return Err(PluginError::Configuration(format!(
+            "LISTEN_FDS value {} exceeds the open file limit of {}",
+            count, fd_limit
+        )));
+    }
+
+    // Names are optional; the service manager reports unnamed sockets as "unknown".
+    let mut names = env.names.as_deref().unwrap_or_default().split(':');
+    Ok((LISTEN_FDS_START..end)
+        .map(|fd| {
+            let name = names.next().filter(|name| !name.is_empty());
+            (fd, name.unwrap_or("unknown").to_string())
+        })
+        .collect())
+}
+
+/// Returns the soft limit on open file descriptors, which bounds inherited descriptors.
+fn open_file_limit() -> u64 {
+    // SAFETY: `limit` is a live local of the size getrlimit expects.
+    let mut limit: libc::rlimit = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0
+        || limit.rlim_cur == libc::RLIM_INFINITY
+    {
+        return u64::MAX;
+    }
+    limit.rlim_cur as u64
+}
+
+/// Takes ownership of an inherited file descriptor, which must be a listening stream
+/// socket in the unix or IP address family.
+pub(crate) fn listener_from_fd(fd: RawFd) -> Result<InheritedListener> {
+    if fd < 0 {
+        return Err(PluginError::Configuration(format!(
+            "Invalid file descriptor {}",
+            fd
+        )));
+    }
+
+    let invalid = |reason: &str| {
+        PluginError::Configuration(format!("Inherited fd {} is not usable: {}", fd, reason))
+    };
+
+    if socket_option(fd, libc::SO_TYPE)? != libc::SOCK_STREAM {
+        return Err(invalid("not a stream socket"));
+    }
+    if socket_option(fd, libc::SO_ACCEPTCONN)? == 0 {
+        return Err(invalid("socket is not listening"));
+    }
+
+    // SAFETY: `storage` is large enough for any address and `len` reports its size.
+    let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    let rc =
+        unsafe { libc::getsockname(fd, &mut storage as *mut _ as *mut libc::sockaddr, &mut len) };
+    if rc != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    // Keep the socket out of any processes the plugin spawns.
+    // SAFETY: fcntl on a descriptor validated above; failures are reported via errno.
+    if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    // SAFETY: the descriptor was handed to this process for serving and is a listening
+    // socket; nothing else in the process owns it.
+    let owned = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    match libc::c_int::from(storage.ss_family) {
+        libc::AF_UNIX => {
+            let listener = std::os::unix::net::UnixListener::from(owned);
+            listener.set_nonblocking(true)?;
+            Ok(InheritedListener::Unix(tokio::net::UnixListener::from_std(
+                listener,
+            )?))
+        }
+        libc::AF_INET | libc::AF_INET6 => {
+            let listener = std::net::TcpListener::from(owned);
+            listener.set_nonblocking(true)?;
+            Ok(InheritedListener::Tcp(tokio::net::TcpListener::from_std(
+                listener,
+            )?))
+        }
+        family => {
+            // Leave descriptors of unknown kinds open for whoever else may use them.
+            std::mem::forget(owned);
+            Err(invalid(&format!("unsupported address family {}", family)))
+        }
+    }
+}
+
+/// Reads an integer `SOL_SOCKET` option, failing if `fd` is not a socket.
+fn socket_option(fd: RawFd, option: libc::c_int) -> Result<libc::c_int> {
+    let mut value: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+
+    // SAFETY: `value` and `len` are live locals of the sizes passed.
+    let rc = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            option,
+            &mut value as *mut _ as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if rc != 0 {
+        let err = std::io::Error::last_os_error();
+        return Err(PluginError::Configuration(format!(
+            "Inherited fd {} is not usable: {}",
+            fd, err
+        )));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PID: u32 = 4242;
+
+    fn env(pid: &str, fds: &str, names: Option<&str>) -> ListenEnv {
+        ListenEnv {
+            pid: Some(pid.to_string()),
+            fds: Some(fds.to_string()),
+            names: names.map(str::to_string),
+        }
+    }
+
+    fn fds(pairs: &[(RawFd, &str)]) -> Vec<(RawFd, String)> {
+        pairs
+            .iter()
+            .map(|(fd, name)| (*fd, name.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn ignores_variables_meant_for_another_process() {
+        for env in [
+            ListenEnv::default(),
+            env("4243", "1", None),
+            env("not-a-pid", "1", None),
+            ListenEnv {
+                fds: None,
+                ..env("4242", "", None)
+            },
+        ] {
+            assert_eq!(listen_fds(&env, PID, 1024).unwrap(), [], "{:?}", env);
+        }
+    }
+
+    #[test]
+    fn rejects_unparsable_or_excessive_counts() {
+        for count in ["", "two", "-1", "1.5", "2147483647", "99999999999"] {
+            let result = listen_fds(&env("4242", count, None), PID, u64::MAX);
+            assert!(
+                matches!(result, Err(PluginError::Configuration(_))),
+                "{}",
+                count
+            );
+        }
+
+        // Descriptors 3 to 1026 could not all be open under a limit of 1024.
+        assert!(listen_fds(&env("4242", "1024", None), PID, 1024).is_err());
+        assert_eq!(
+            listen_fds(&env("4242", "1021", None), PID, 1024)
+                .unwrap()
+                .len(),
+            1021
+        );
+    }
+
+    #[test]
+    fn assigns_names_in_order() {
+        assert_eq!(
+            listen_fds(&env("4242", "0", Some("grpc")), PID, 1024).unwrap(),
+            []
+        );
+        assert_eq!(
+            listen_fds(&env("4242", "2", None), PID, 1024).unwrap(),
+            fds(&[(3, "unknown"), (4, "unknown")])
+        );
+        assert_eq!(
+            listen_fds(&env("4242", "4", Some("grpc::admin")), PID, 1024).unwrap(),
+            fds(&[(3, "grpc"), (4, "unknown"), (5, "admin"), (6, "unknown")])
+        );
+    }
+
+    #[test]
+    fn selects_a_descriptor() {
+        let passed = fds(&[(3, "grpc"), (4, "admin")]);
+
+        assert_eq!(select_fd("fd", "7", &passed).unwrap(), Some(7));
+        assert_eq!(select_fd("fd", "admin", &passed).unwrap(), Some(4));
+        assert!(select_fd("fd", "metrics", &passed).is_err());
+
+        assert_eq!(select_fd("unix", "/tmp/p.sock", &passed).unwrap(), Some(3));
+        assert_eq!(select_fd("tcp", "127.0.0.1:0", &[]).unwrap(), None);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::plugin::Plugin;
+use crate::proto::{Flow, HttpResponse};
+use bytes::Bytes;
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::Response;
+
+/// Header in which `GetCapabilities` responses list the flows whose bodies the plugin does
+/// not read (comma-separated, e.g. `FLOW_REQUEST`), see [`Plugin::needs_body()`].
+///
+/// The host may send messages of those flows with an empty body. A host doing so must also
+/// send [`OMIT_UNCHANGED_BODY_HEADER`], so it keeps its own copy of the body.
+pub const BODYLESS_FLOWS_HEADER: &str = "mcpd-plugin-bodyless-flows";
+
+/// Header (`true`) in which the host asks, on `HandleRequest`/`HandleResponse` calls, for
+/// bodies the plugin passed through unchanged to be left out of the result.
+pub const OMIT_UNCHANGED_BODY_HEADER: &str = "mcpd-plugin-omit-unchanged-body";
+
+/// Header (`true`) set on `HandleRequest`/`HandleResponse` results whose body was left out
+/// because the plugin passed it through unchanged. The host keeps its original body.
+pub const BODY_UNCHANGED_HEADER: &str = "mcpd-plugin-body-unchanged";
+
+/// Lists the flows whose bodies `plugin` does not read in the metadata of `GetCapabilities`.
+pub(crate) fn report_bodyless<P: Plugin>(metadata: &mut MetadataMap, plugin: &P) {
+    let flows: Vec<&str> = [Flow::Request, Flow::Response]
+        .into_iter()
+        .filter(|flow| !plugin.needs_body(*flow))
+        .map(|flow| flow.as_str_name())
+        .collect();
+    if flows.is_empty() {
+        return;
+    }
+    if let Ok(value) = MetadataValue::try_from(flows.join(",")) {
+        metadata.insert(BODYLESS_FLOWS_HEADER, value);
+    }
+}
+
+/// Returns whether the host asked for unchanged bodies to be left out.
+pub(crate) fn omission_requested(metadata: &MetadataMap) -> bool {
+    metadata
+        .get(OMIT_UNCHANGED_BODY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+}
+
+/// Leaves out the body of a continuing `HandleRequest` result's `modified_request` if it is
+/// the body the plugin received.
+pub(crate) fn omit_unchanged_request(response: &mut Response<HttpResponse>, original: &Bytes) {
+    let message = response.get_mut();
+    if !message.r#continue {
+        return;
+    }
+    let Some(request) = &mut message.modified_request else {
+        return;
+    };
+    if omit(&mut request.body, original) {
+        mark_unchanged(response.metadata_mut());
+    }
+}
+
+/// Leaves out the body of a continuing `HandleResponse` result if it is the body the plugin
+/// received.
+pub(crate) fn omit_unchanged_response(response: &mut Response<HttpResponse>, original: &Bytes) {
+    let message = response.get_mut();
+    if message.r#continue && omit(&mut message.body, original) {
+        mark_unchanged(response.metadata_mut());
+    }
+}
+
+/// Empties `body` if it is `original` passed through, and returns whether it was.
+///
+/// Bodies are compared by buffer rather than content: passing a body through, or cloning
+/// it, shares the received buffer, while any modification creates a new one.
+fn omit(body: &mut Bytes, original: &Bytes) -> bool {
+    let unchanged =
+        body.len() == original.len() && (body.is_empty() || body.as_ptr() == original.as_ptr());
+    if unchanged {
+        *body = Bytes::new();
+    }
+    unchanged
+}
+
+fn mark_unchanged(metadata: &mut MetadataMap) {
+    metadata.insert(BODY_UNCHANGED_HEADER, MetadataValue::from_static("true"));
+}
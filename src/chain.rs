@@ -0,0 +1,335 @@
+use crate::constants::{FLOW_REQUEST, FLOW_RESPONSE};
+use crate::plugin::Plugin;
+use crate::proto::{Capabilities, Flow, HttpRequest, HttpResponse, Metadata, PluginConfig};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tonic::metadata::MetadataMap;
+use tonic::{Request, Response, Status};
+
+/// A [`Plugin`] that runs several plugins in order, so one binary can provide e.g.
+/// authentication, rate limiting and header injection together.
+///
+/// - **Requests** go through every plugin that declares [`FLOW_REQUEST`]. A plugin's
+///   `modified_request` becomes the input of the next one, headers returned alongside
+///   `continue: true` are merged, and the first `continue: false` response is returned with
+///   the headers merged so far, its own headers taking precedence.
+/// - **Responses** go through every plugin that declares [`FLOW_RESPONSE`], each receiving
+///   the response returned by the previous one, again stopping at the first `continue: false`.
+/// - **Metadata and capabilities** are merged: names and versions are joined with `+`, and
+///   flows are the union of all plugins' flows. The chain needs a flow's bodies if any plugin
+///   participating in that flow does, see [`Plugin::needs_body()`].
+/// - **Configuration** is fanned out by namespace: a plugin added as `"auth"` receives the
+///   `custom_config` keys prefixed with `auth.`, with the prefix removed (so `auth.valid_tokens`
+///   arrives as `valid_tokens`). Telemetry settings are passed to every plugin. Each plugin's
+///   flows are asked for again after it is configured, so they may depend on its configuration.
+/// - **Lifecycle and health** calls go to every plugin; the first error is returned, prefixed
+///   with the plugin's namespace.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use mcpd_plugins_sdk::{serve, Plugin, PluginChain};
+///
+/// struct AuthPlugin;
+/// struct RateLimitPlugin;
+///
+/// #[tonic::async_trait]
+/// impl Plugin for AuthPlugin {}
+///
+/// #[tonic::async_trait]
+/// impl Plugin for RateLimitPlugin {}
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let chain = PluginChain::new()
+///         .add("auth", AuthPlugin)
+///         .add("ratelimit", RateLimitPlugin);
+///     serve(chain, None).await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Default)]
+pub struct PluginChain {
+    links: Vec<Link>,
+    metadata: Option<Metadata>,
+}
+
+/// A plugin in the chain, with its namespace and cached flows.
+struct Link {
+    namespace: String,
+    plugin: Box<dyn Plugin>,
+    flows: RwLock<Option<Vec<i32>>>,
+}
+
+impl PluginChain {
+    /// Creates an empty chain.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `plugin` to the chain under `namespace`.
+    ///
+    /// The namespace selects the plugin's `custom_config` keys and prefixes its errors.
+    pub fn add<P: Plugin>(mut self, namespace: impl Into<String>, plugin: P) -> Self {
+        self.links.push(Link {
+            namespace: namespace.into(),
+            plugin: Box::new(plugin),
+            flows: RwLock::new(None),
+        });
+        self
+    }
+
+    /// Reports `metadata` for the chain instead of merging the plugins' metadata.
+    pub fn metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Returns the links that participate in `flow`.
+    async fn links_for(&self, flow: i32) -> Result<Vec<&Link>, Status> {
+        let mut links = Vec::with_capacity(self.links.len());
+        for link in &self.links {
+            if link.flows().await?.contains(&flow) {
+                links.push(link);
+            }
+        }
+        Ok(links)
+    }
+}
+
+impl Link {
+    /// Returns the flows this plugin declares, asking it on first use after creation or
+    /// configuration.
+    async fn flows(&self) -> Result<Vec<i32>, Status> {
+        if let Some(flows) = self.cached_flows() {
+            return Ok(flows);
+        }
+
+        let flows = self
+            .plugin
+            .get_capabilities(Request::new(()))
+            .await
+            .map_err(|status| self.prefixed(status))?
+            .into_inner()
+            .flows;
+        *self.flows.write().unwrap_or_else(|err| err.into_inner()) = Some(flows.clone());
+        Ok(flows)
+    }
+
+    fn cached_flows(&self) -> Option<Vec<i32>> {
+        self.flows
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    /// Forgets the cached flows, which may depend on the configuration.
+    fn reset_flows(&self) {
+        *self.flows.write().unwrap_or_else(|err| err.into_inner()) = None;
+    }
+
+    /// Returns the `custom_config` entries for this plugin with the namespace removed.
+    fn custom_config(&self, custom_config: &HashMap<String, String>) -> HashMap<String, String> {
+        custom_config
+            .iter()
+            .filter_map(|(key, value)| {
+                let key = key
+                    .strip_prefix(self.namespace.as_str())?
+                    .strip_prefix('.')?;
+                Some((key.to_string(), value.clone()))
+            })
+            .collect()
+    }
+
+    /// Adds the namespace to an error returned by this plugin.
+    fn prefixed(&self, status: Status) -> Status {
+        Status::new(
+            status.code(),
+            format!("{}: {}", self.namespace, status.message()),
+        )
+    }
+}
+
+/// Builds a request for an inner plugin carrying the caller's metadata.
+fn inner_request<T>(message: T, metadata: &MetadataMap) -> Request<T> {
+    let mut request = Request::new(message);
+    *request.metadata_mut() = metadata.clone();
+    request
+}
+
+/// Joins the non-empty values of a metadata field.
+fn join<'a>(values: impl Iterator<Item = &'a String>, separator: &str) -> String {
+    values
+        .filter(|value| !value.is_empty())
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+#[tonic::async_trait]
+impl Plugin for PluginChain {
+    async fn get_metadata(&self, request: Request<()>) -> Result<Response<Metadata>, Status> {
+        if let Some(metadata) = &self.metadata {
+            return Ok(Response::new(metadata.clone()));
+        }
+
+        let mut all = Vec::with_capacity(self.links.len());
+        for link in &self.links {
+            let metadata = link
+                .plugin
+                .get_metadata(inner_request((), request.metadata()))
+                .await
+                .map_err(|status| link.prefixed(status))?;
+            all.push(metadata.into_inner());
+        }
+
+        Ok(Response::new(Metadata {
+            name: join(all.iter().map(|m| &m.name), "+"),
+            version: join(all.iter().map(|m| &m.version), "+"),
+            description: join(all.iter().map(|m| &m.description), "; "),
+            commit_hash: join(all.iter().map(|m| &m.commit_hash), "+"),
+            build_date: join(all.iter().map(|m| &m.build_date), "+"),
+        }))
+    }
+
+    async fn get_capabilities(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<Capabilities>, Status> {
+        let mut flows = Vec::new();
+        for link in &self.links {
+            for flow in link.flows().await? {
+                if !flows.contains(&flow) {
+                    flows.push(flow);
+                }
+            }
+        }
+        Ok(Response::new(Capabilities { flows }))
+    }
+
+    fn needs_body(&self, flow: Flow) -> bool {
+        // Before the flows are known, every plugin is assumed to participate.
+        self.links.iter().any(|link| {
+            link.cached_flows()
+                .is_none_or(|flows| flows.contains(&(flow as i32)))
+                && link.plugin.needs_body(flow)
+        })
+    }
+
+    async fn configure(&self, request: Request<PluginConfig>) -> Result<Response<()>, Status> {
+        let metadata = request.metadata().clone();
+        let config = request.into_inner();
+
+        for link in &self.links {
+            let inner = PluginConfig {
+                telemetry: config.telemetry.clone(),
+                custom_config: link.custom_config(&config.custom_config),
+            };
+            let result = link.plugin.configure(inner_request(inner, &metadata)).await;
+            // Flows may depend on the configuration, so ask again on next use.
+            link.reset_flows();
+            result.map_err(|status| link.prefixed(status))?;
+        }
+
+        Ok(Response::new(()))
+    }
+
+    async fn stop(&self, request: Request<()>) -> Result<Response<()>, Status> {
+        // Stop every plugin, even if an earlier one fails.
+        let mut first_error = None;
+        for link in &self.links {
+            if let Err(status) = link
+                .plugin
+                .stop(inner_request((), request.metadata()))
+                .await
+            {
+                first_error.get_or_insert(link.prefixed(status));
+            }
+        }
+
+        match first_error {
+            Some(status) => Err(status),
+            None => Ok(Response::new(())),
+        }
+    }
+
+    async fn check_health(&self, request: Request<()>) -> Result<Response<()>, Status> {
+        for link in &self.links {
+            link.plugin
+                .check_health(inner_request((), request.metadata()))
+                .await
+                .map_err(|status| link.prefixed(status))?;
+        }
+        Ok(Response::new(()))
+    }
+
+    async fn check_ready(&self, request: Request<()>) -> Result<Response<()>, Status> {
+        for link in &self.links {
+            link.plugin
+                .check_ready(inner_request((), request.metadata()))
+                .await
+                .map_err(|status| link.prefixed(status))?;
+        }
+        Ok(Response::new(()))
+    }
+
+    async fn handle_request(
+        &self,
+        request: Request<HttpRequest>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        let metadata = request.metadata().clone();
+        let mut req = request.into_inner();
+        let mut modified = false;
+        let mut headers = HashMap::new();
+
+        for link in self.links_for(FLOW_REQUEST as i32).await? {
+            let resp = link
+                .plugin
+                .handle_request(inner_request(req.clone(), &metadata))
+                .await
+                .map_err(|status| link.prefixed(status))?
+                .into_inner();
+
+            headers.extend(resp.headers);
+            if !resp.r#continue {
+                // Earlier plugins' headers still apply, overridden by the rejecting plugin's.
+                return Ok(Response::new(HttpResponse { headers, ..resp }));
+            }
+
+            if let Some(modified_request) = resp.modified_request {
+                req = modified_request;
+                modified = true;
+            }
+        }
+
+        Ok(Response::new(HttpResponse {
+            r#continue: true,
+            headers,
+            modified_request: modified.then_some(req),
+            ..Default::default()
+        }))
+    }
+
+    async fn handle_response(
+        &self,
+        response: Request<HttpResponse>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        let metadata = response.metadata().clone();
+        let mut resp = response.into_inner();
+
+        for link in self.links_for(FLOW_RESPONSE as i32).await? {
+            resp = link
+                .plugin
+                .handle_response(inner_request(resp, &metadata))
+                .await
+                .map_err(|status| link.prefixed(status))?
+                .into_inner();
+
+            if !resp.r#continue {
+                break;
+            }
+        }
+
+        Ok(Response::new(resp))
+    }
+}
@@ -0,0 +1,481 @@
+use crate::proto::plugin_server::{Plugin as PluginService, PluginServer};
+use crate::{PluginError, Result};
+use bytes::Bytes;
+use clap::ValueEnum;
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use tonic::body::BoxBody;
+use tonic::codec::CompressionEncoding;
+use tonic::codegen::BoxFuture;
+use tonic::Status;
+use tower::Service;
+use tracing::warn;
+
+/// A gRPC message compression algorithm.
+///
+/// Each algorithm is compiled in by the feature of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+#[non_exhaustive]
+pub enum Compression {
+    /// gzip, enabled by the `gzip` feature.
+    Gzip,
+
+    /// zstd, enabled by the `zstd` feature.
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the algorithm name as used in `grpc-encoding` and on the command line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    /// Returns the tonic encoding, or an error if its feature is not enabled.
+    fn encoding(&self) -> Result<CompressionEncoding> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Ok(CompressionEncoding::Gzip),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(CompressionEncoding::Zstd),
+            #[allow(unreachable_patterns)]
+            _ => Err(PluginError::Configuration(format!(
+                "{} compression requires the `{}` feature of mcpd-plugins-sdk",
+                self.as_str(),
+                self.as_str()
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for Compression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Message size limits and compression of the plugin service.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CodecOptions {
+    pub(crate) max_decoding_message_size: Option<usize>,
+    pub(crate) max_encoding_message_size: Option<usize>,
+    pub(crate) accept_compressed: Vec<Compression>,
+    pub(crate) send_compressed: Option<Compression>,
+}
+
+impl CodecOptions {
+    /// Checks that the limits are positive and every algorithm is compiled in.
+    pub(crate) fn validate(&self) -> Result<()> {
+        for (flag, limit) in [
+            ("max-decoding-message-size", self.max_decoding_message_size),
+            ("max-encoding-message-size", self.max_encoding_message_size),
+        ] {
+            if limit == Some(0) {
+                return Err(PluginError::Configuration(format!(
+                    "{} must be greater than zero",
+                    flag
+                )));
+            }
+        }
+        for compression in self.accept_compressed.iter().chain(&self.send_compressed) {
+            compression.encoding()?;
+        }
+        Ok(())
+    }
+
+    /// Applies the options to the generated plugin server.
+    pub(crate) fn apply<T: PluginService>(
+        &self,
+        server: PluginServer<T>,
+    ) -> Result<PluginServer<T>> {
+        let mut server = server;
+        if let Some(limit) = self.max_decoding_message_size {
+            server = server.max_decoding_message_size(limit);
+        }
+        if let Some(limit) = self.max_encoding_message_size {
+            server = server.max_encoding_message_size(limit);
+        }
+        for compression in &self.accept_compressed {
+            server = server.accept_compressed(compression.encoding()?);
+        }
+        if let Some(compression) = &self.send_compressed {
+            server = server.send_compressed(compression.encoding()?);
+        }
+        Ok(server)
+    }
+}
+
+/// Returns the status to fail with if a handler's result exceeds the encoding limit.
+///
+/// tonic reports an oversized response to the host only, after the handler has returned;
+/// failing here also logs which RPC produced it and how to raise the limit.
+pub(crate) fn encoding_limit_exceeded<M: prost::Message>(
+    rpc: &str,
+    message: &M,
+    limit: Option<usize>,
+) -> Option<Status> {
+    let limit = limit?;
+    let len = message.encoded_len();
+    if len <= limit {
+        return None;
+    }
+    warn!(
+        "{} result of {} bytes exceeds the {} byte message limit, raise it with --max-encoding-message-size",
+        rpc, len, limit
+    );
+    Some(Status::out_of_range(format!(
+        "{} result of {} bytes exceeds the plugin's {} byte message limit",
+        rpc, len, limit
+    )))
+}
+
+/// tonic's decoding limit when none is configured.
+const DEFAULT_MAX_DECODING_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Length of a gRPC frame header: a compression flag and a big-endian message length.
+const FRAME_HEADER_LEN: usize = 5;
+
+/// Logs requests the plugin service rejected for exceeding the decoding limit.
+///
+/// tonic answers those before the plugin sees them, so nothing would be logged otherwise.
+/// The message lengths are read from the request's frame headers as tonic consumes the
+/// body, and compared with the limit once the service has answered.
+#[derive(Clone)]
+pub(crate) struct LogOversized<S> {
+    service: S,
+    limit: usize,
+}
+
+impl<S> LogOversized<S> {
+    /// Wraps `service`, whose decoding limit is `limit` (tonic's default if `None`).
+    pub(crate) fn new(service: S, limit: Option<usize>) -> Self {
+        Self {
+            service,
+            limit: limit.unwrap_or(DEFAULT_MAX_DECODING_MESSAGE_SIZE),
+        }
+    }
+}
+
+impl<S> Service<http::Request<BoxBody>> for LogOversized<S>
+where
+    S: Service<http::Request<BoxBody>, Response = http::Response<BoxBody>, Error = Infallible>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Infallible>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Infallible>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        let path = request.uri().path().to_string();
+        let largest = Arc::new(AtomicUsize::new(0));
+        let request =
+            request.map(|body| tonic::body::boxed(FrameLengths::new(body, largest.clone())));
+        let future = self.service.call(request);
+        let limit = self.limit;
+        Box::pin(async move {
+            let response = future.await?;
+            decoding_limit_exceeded(&path, largest.load(Ordering::Relaxed), limit);
+            Ok(response)
+        })
+    }
+}
+
+/// Logs and returns whether a request message of `len` bytes exceeds the decoding limit.
+fn decoding_limit_exceeded(path: &str, len: usize, limit: usize) -> bool {
+    if len <= limit {
+        return false;
+    }
+    warn!(
+        "Rejected {}: message of {} bytes exceeds the {} byte limit, raise it with --max-decoding-message-size",
+        path, len, limit
+    );
+    true
+}
+
+/// Request body recording the largest message length announced by its frame headers.
+struct FrameLengths {
+    body: BoxBody,
+    header: [u8; FRAME_HEADER_LEN],
+    header_read: usize,
+    message_left: usize,
+    largest: Arc<AtomicUsize>,
+}
+
+impl FrameLengths {
+    fn new(body: BoxBody, largest: Arc<AtomicUsize>) -> Self {
+        Self {
+            body,
+            header: [0; FRAME_HEADER_LEN],
+            header_read: 0,
+            message_left: 0,
+            largest,
+        }
+    }
+
+    /// Reads the frame headers in the next chunk of the body, skipping the messages.
+    fn observe(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.message_left > 0 {
+                let skipped = self.message_left.min(data.len());
+                self.message_left -= skipped;
+                data = &data[skipped..];
+                continue;
+            }
+            let read = (FRAME_HEADER_LEN - self.header_read).min(data.len());
+            self.header[self.header_read..self.header_read + read].copy_from_slice(&data[..read]);
+            self.header_read += read;
+            data = &data[read..];
+            if self.header_read == FRAME_HEADER_LEN {
+                let len = u32::from_be_bytes([
+                    self.header[1],
+                    self.header[2],
+                    self.header[3],
+                    self.header[4],
+                ]) as usize;
+                self.largest.fetch_max(len, Ordering::Relaxed);
+                self.header_read = 0;
+                self.message_left = len;
+            }
+        }
+    }
+}
+
+impl http_body::Body for FrameLengths {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<std::result::Result<http_body::Frame<Bytes>, Status>>> {
+        let frame = ready!(Pin::new(&mut self.body).poll_frame(cx));
+        if let Some(Ok(frame)) = &frame {
+            if let Some(data) = frame.data_ref() {
+                self.observe(data);
+            }
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.body.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::{Plugin, PluginAdapter};
+    use crate::proto::{HttpRequest, HttpResponse};
+    use prost::Message;
+    use std::collections::VecDeque;
+    use std::io::Write;
+    use std::sync::Mutex;
+    use tonic::Request;
+
+    struct Passthrough;
+
+    impl Plugin for Passthrough {}
+
+    /// Request body made of the given chunks.
+    struct Chunks(VecDeque<Bytes>);
+
+    impl http_body::Body for Chunks {
+        type Data = Bytes;
+        type Error = Infallible;
+
+        fn poll_frame(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Option<std::result::Result<http_body::Frame<Bytes>, Infallible>>> {
+            Poll::Ready(
+                self.0
+                    .pop_front()
+                    .map(|chunk| Ok(http_body::Frame::data(chunk))),
+            )
+        }
+    }
+
+    /// Log output captured from the current thread.
+    #[derive(Clone, Default)]
+    struct Logs(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Logs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Logs {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    /// Frames `message` as a gRPC message.
+    fn frame(message: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0];
+        frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        frame.extend_from_slice(message);
+        frame
+    }
+
+    fn largest_frame(chunks: &[&[u8]]) -> usize {
+        let largest = Arc::new(AtomicUsize::new(0));
+        let mut lengths = FrameLengths::new(tonic::body::empty_body(), largest.clone());
+        for chunk in chunks {
+            lengths.observe(chunk);
+        }
+        largest.load(Ordering::Relaxed)
+    }
+
+    /// Sends `body` to `HandleRequest` on a server whose decoding limit is `limit`, and
+    /// returns the response status and the log output.
+    async fn handle_request(limit: usize, body: Vec<u8>) -> (Option<Status>, String) {
+        let logs = Logs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let server = PluginServer::new(PluginAdapter::new(Passthrough));
+        let options = CodecOptions {
+            max_decoding_message_size: Some(limit),
+            ..Default::default()
+        };
+        let mut service = LogOversized::new(
+            options.apply(server).unwrap(),
+            options.max_decoding_message_size,
+        );
+        let request = http::Request::builder()
+            .uri("/mozilla.mcpd.plugins.v1.Plugin/HandleRequest")
+            .header("content-type", "application/grpc")
+            .body(tonic::body::boxed(Chunks(VecDeque::from([Bytes::from(
+                body,
+            )]))))
+            .unwrap();
+        let response = service.call(request).await.unwrap();
+        let status = Status::from_header_map(response.headers());
+        (status, logs.contents())
+    }
+
+    #[test]
+    fn allows_results_within_the_encoding_limit() {
+        let response = HttpResponse {
+            body: Bytes::from(vec![0; 16]),
+            ..Default::default()
+        };
+        let len = response.encoded_len();
+        assert!(encoding_limit_exceeded("HandleResponse", &response, None).is_none());
+        assert!(encoding_limit_exceeded("HandleResponse", &response, Some(len)).is_none());
+    }
+
+    #[test]
+    fn fails_results_over_the_encoding_limit() {
+        let response = HttpResponse {
+            body: Bytes::from(vec![0; 16]),
+            ..Default::default()
+        };
+        let len = response.encoded_len();
+        let status = encoding_limit_exceeded("HandleResponse", &response, Some(len - 1)).unwrap();
+        assert_eq!(status.code(), tonic::Code::OutOfRange);
+        assert_eq!(
+            status.message(),
+            format!(
+                "HandleResponse result of {} bytes exceeds the plugin's {} byte message limit",
+                len,
+                len - 1
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn adapter_fails_results_over_the_encoding_limit() {
+        let adapter = PluginAdapter::new(Passthrough).max_encoding_message_size(Some(8));
+
+        let small = HttpResponse {
+            r#continue: true,
+            ..Default::default()
+        };
+        assert!(adapter.handle_response(Request::new(small)).await.is_ok());
+
+        let large = HttpResponse {
+            r#continue: true,
+            body: Bytes::from(vec![0; 16]),
+            ..Default::default()
+        };
+        let status = adapter
+            .handle_response(Request::new(large))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::OutOfRange);
+    }
+
+    #[test]
+    fn reads_frame_lengths_across_chunks() {
+        let mut body = frame(&[1; 3]);
+        body.extend(frame(&[2; 300]));
+        body.extend(frame(&[]));
+
+        assert_eq!(largest_frame(&[&body]), 300);
+        let bytes: Vec<&[u8]> = body.chunks(1).collect();
+        assert_eq!(largest_frame(&bytes), 300);
+        let (first, rest) = body.split_at(10);
+        assert_eq!(largest_frame(&[first, rest]), 300);
+        assert_eq!(largest_frame(&[]), 0);
+    }
+
+    #[test]
+    fn compares_lengths_with_the_decoding_limit() {
+        assert!(!decoding_limit_exceeded("/p", 15, 16));
+        assert!(!decoding_limit_exceeded("/p", 16, 16));
+        assert!(decoding_limit_exceeded("/p", 17, 16));
+    }
+
+    #[tokio::test]
+    async fn logs_requests_over_the_decoding_limit() {
+        let (status, logs) = handle_request(16, frame(&[0; 64])).await;
+        assert_eq!(status.unwrap().code(), tonic::Code::OutOfRange);
+        assert!(
+            logs.contains(
+                "Rejected /mozilla.mcpd.plugins.v1.Plugin/HandleRequest: message of 64 bytes \
+                 exceeds the 16 byte limit"
+            ),
+            "{}",
+            logs
+        );
+    }
+
+    #[tokio::test]
+    async fn does_not_log_requests_within_the_decoding_limit() {
+        let request = HttpRequest {
+            body: Bytes::from(vec![0; 8]),
+            ..Default::default()
+        };
+        let body = frame(&request.encode_to_vec());
+        let (_, logs) = handle_request(request.encoded_len(), body).await;
+        assert!(!logs.contains("Rejected"), "{}", logs);
+    }
+}
@@ -0,0 +1,85 @@
+use clap::ValueEnum;
+use std::num::NonZeroUsize;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// What happens to a request or response when the in-flight limit has been reached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OverloadPolicy {
+    /// Wait for an in-flight call to finish (bounded by the RPC deadline, if any).
+    #[default]
+    Queue,
+
+    /// Reject immediately with `ResourceExhausted`.
+    Shed,
+}
+
+impl OverloadPolicy {
+    /// Returns the policy name as used on the command line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverloadPolicy::Queue => "queue",
+            OverloadPolicy::Shed => "shed",
+        }
+    }
+}
+
+/// Bounds the number of `HandleRequest`/`HandleResponse` calls running at once.
+#[derive(Debug)]
+pub(crate) struct ConcurrencyLimit {
+    semaphore: Semaphore,
+    max_in_flight: usize,
+    policy: OverloadPolicy,
+}
+
+impl ConcurrencyLimit {
+    /// Creates a limit of `max_in_flight` concurrent calls.
+    pub(crate) fn new(max_in_flight: NonZeroUsize, policy: OverloadPolicy) -> Self {
+        let max_in_flight = max_in_flight.get();
+        Self {
+            semaphore: Semaphore::new(max_in_flight),
+            max_in_flight,
+            policy,
+        }
+    }
+
+    /// Claims a slot for one call, or returns `None` when shedding and no slot is free.
+    ///
+    /// The slot is released when the returned permit is dropped.
+    pub(crate) async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        match self.policy {
+            OverloadPolicy::Queue => self.semaphore.acquire().await.ok(),
+            OverloadPolicy::Shed => self.semaphore.try_acquire().ok(),
+        }
+    }
+
+    /// Returns true when every slot is taken.
+    pub(crate) fn is_saturated(&self) -> bool {
+        self.semaphore.available_permits() == 0
+    }
+
+    pub(crate) fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    pub(crate) fn policy(&self) -> OverloadPolicy {
+        self.policy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn sheds_once_saturated() {
+        let limit = ConcurrencyLimit::new(NonZeroUsize::MIN, OverloadPolicy::Shed);
+        let permit = limit.acquire().await;
+        assert!(permit.is_some());
+        assert!(limit.is_saturated());
+        assert!(limit.acquire().await.is_none());
+
+        drop(permit);
+        assert!(!limit.is_saturated());
+        assert!(limit.acquire().await.is_some());
+    }
+}
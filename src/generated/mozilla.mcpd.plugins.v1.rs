@@ -35,8 +35,8 @@ pub struct HttpRequest {
         ::prost::alloc::string::String,
         ::prost::alloc::string::String,
     >,
-    #[prost(bytes = "vec", tag = "5")]
-    pub body: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "bytes", tag = "5")]
+    pub body: ::prost::bytes::Bytes,
     #[prost(string, tag = "6")]
     pub remote_addr: ::prost::alloc::string::String,
     #[prost(string, tag = "7")]
@@ -52,8 +52,8 @@ pub struct HttpResponse {
         ::prost::alloc::string::String,
         ::prost::alloc::string::String,
     >,
-    #[prost(bytes = "vec", tag = "3")]
-    pub body: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "bytes", tag = "3")]
+    pub body: ::prost::bytes::Bytes,
     /// If true, continue to next plugin/handler; if false, short-circuit
     #[prost(bool, tag = "4")]
     pub r#continue: bool,
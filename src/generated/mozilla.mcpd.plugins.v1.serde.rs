@@ -0,0 +1,953 @@
+impl serde::Serialize for Capabilities {
+    #[allow(deprecated)]
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut len = 0;
+        if !self.flows.is_empty() {
+            len += 1;
+        }
+        let mut struct_ser = serializer.serialize_struct("mozilla.mcpd.plugins.v1.Capabilities", len)?;
+        if !self.flows.is_empty() {
+            let v = self.flows.iter().cloned().map(|v| {
+                Flow::try_from(v)
+                    .map_err(|_| serde::ser::Error::custom(format!("Invalid variant {}", v)))
+                }).collect::<Result<Vec<_>, _>>()?;
+            struct_ser.serialize_field("flows", &v)?;
+        }
+        struct_ser.end()
+    }
+}
+impl<'de> serde::Deserialize<'de> for Capabilities {
+    #[allow(deprecated)]
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        const FIELDS: &[&str] = &[
+            "flows",
+        ];
+
+        #[allow(clippy::enum_variant_names)]
+        enum GeneratedField {
+            Flows,
+        }
+        impl<'de> serde::Deserialize<'de> for GeneratedField {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<GeneratedField, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct GeneratedVisitor;
+
+                impl<'de> serde::de::Visitor<'de> for GeneratedVisitor {
+                    type Value = GeneratedField;
+
+                    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        write!(formatter, "expected one of: {:?}", &FIELDS)
+                    }
+
+                    #[allow(unused_variables)]
+                    fn visit_str<E>(self, value: &str) -> std::result::Result<GeneratedField, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        match value {
+                            "flows" => Ok(GeneratedField::Flows),
+                            _ => Err(serde::de::Error::unknown_field(value, FIELDS)),
+                        }
+                    }
+                }
+                deserializer.deserialize_identifier(GeneratedVisitor)
+            }
+        }
+        struct GeneratedVisitor;
+        impl<'de> serde::de::Visitor<'de> for GeneratedVisitor {
+            type Value = Capabilities;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                formatter.write_str("struct mozilla.mcpd.plugins.v1.Capabilities")
+            }
+
+            fn visit_map<V>(self, mut map_: V) -> std::result::Result<Capabilities, V::Error>
+                where
+                    V: serde::de::MapAccess<'de>,
+            {
+                let mut flows__ = None;
+                while let Some(k) = map_.next_key()? {
+                    match k {
+                        GeneratedField::Flows => {
+                            if flows__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("flows"));
+                            }
+                            flows__ = Some(map_.next_value::<Vec<Flow>>()?.into_iter().map(|x| x as i32).collect());
+                        }
+                    }
+                }
+                Ok(Capabilities {
+                    flows: flows__.unwrap_or_default(),
+                })
+            }
+        }
+        deserializer.deserialize_struct("mozilla.mcpd.plugins.v1.Capabilities", FIELDS, GeneratedVisitor)
+    }
+}
+impl serde::Serialize for Flow {
+    #[allow(deprecated)]
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let variant = match self {
+            Self::Request => "FLOW_REQUEST",
+            Self::Response => "FLOW_RESPONSE",
+        };
+        serializer.serialize_str(variant)
+    }
+}
+impl<'de> serde::Deserialize<'de> for Flow {
+    #[allow(deprecated)]
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        const FIELDS: &[&str] = &[
+            "FLOW_REQUEST",
+            "FLOW_RESPONSE",
+        ];
+
+        struct GeneratedVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for GeneratedVisitor {
+            type Value = Flow;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(formatter, "expected one of: {:?}", &FIELDS)
+            }
+
+            fn visit_i64<E>(self, v: i64) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                i32::try_from(v)
+                    .ok()
+                    .and_then(|x| x.try_into().ok())
+                    .ok_or_else(|| {
+                        serde::de::Error::invalid_value(serde::de::Unexpected::Signed(v), &self)
+                    })
+            }
+
+            fn visit_u64<E>(self, v: u64) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                i32::try_from(v)
+                    .ok()
+                    .and_then(|x| x.try_into().ok())
+                    .ok_or_else(|| {
+                        serde::de::Error::invalid_value(serde::de::Unexpected::Unsigned(v), &self)
+                    })
+            }
+
+            fn visit_str<E>(self, value: &str) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                match value {
+                    "FLOW_REQUEST" => Ok(Flow::Request),
+                    "FLOW_RESPONSE" => Ok(Flow::Response),
+                    _ => Err(serde::de::Error::unknown_variant(value, FIELDS)),
+                }
+            }
+        }
+        deserializer.deserialize_any(GeneratedVisitor)
+    }
+}
+impl serde::Serialize for HttpRequest {
+    #[allow(deprecated)]
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut len = 0;
+        if !self.method.is_empty() {
+            len += 1;
+        }
+        if !self.url.is_empty() {
+            len += 1;
+        }
+        if !self.path.is_empty() {
+            len += 1;
+        }
+        if !self.headers.is_empty() {
+            len += 1;
+        }
+        if !self.body.is_empty() {
+            len += 1;
+        }
+        if !self.remote_addr.is_empty() {
+            len += 1;
+        }
+        if !self.request_uri.is_empty() {
+            len += 1;
+        }
+        let mut struct_ser = serializer.serialize_struct("mozilla.mcpd.plugins.v1.HTTPRequest", len)?;
+        if !self.method.is_empty() {
+            struct_ser.serialize_field("method", &self.method)?;
+        }
+        if !self.url.is_empty() {
+            struct_ser.serialize_field("url", &self.url)?;
+        }
+        if !self.path.is_empty() {
+            struct_ser.serialize_field("path", &self.path)?;
+        }
+        if !self.headers.is_empty() {
+            struct_ser.serialize_field("headers", &self.headers)?;
+        }
+        if !self.body.is_empty() {
+            #[allow(clippy::needless_borrow)]
+            struct_ser.serialize_field("body", pbjson::private::base64::encode(&self.body).as_str())?;
+        }
+        if !self.remote_addr.is_empty() {
+            struct_ser.serialize_field("remoteAddr", &self.remote_addr)?;
+        }
+        if !self.request_uri.is_empty() {
+            struct_ser.serialize_field("requestUri", &self.request_uri)?;
+        }
+        struct_ser.end()
+    }
+}
+impl<'de> serde::Deserialize<'de> for HttpRequest {
+    #[allow(deprecated)]
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        const FIELDS: &[&str] = &[
+            "method",
+            "url",
+            "path",
+            "headers",
+            "body",
+            "remote_addr",
+            "remoteAddr",
+            "request_uri",
+            "requestUri",
+        ];
+
+        #[allow(clippy::enum_variant_names)]
+        enum GeneratedField {
+            Method,
+            Url,
+            Path,
+            Headers,
+            Body,
+            RemoteAddr,
+            RequestUri,
+        }
+        impl<'de> serde::Deserialize<'de> for GeneratedField {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<GeneratedField, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct GeneratedVisitor;
+
+                impl<'de> serde::de::Visitor<'de> for GeneratedVisitor {
+                    type Value = GeneratedField;
+
+                    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        write!(formatter, "expected one of: {:?}", &FIELDS)
+                    }
+
+                    #[allow(unused_variables)]
+                    fn visit_str<E>(self, value: &str) -> std::result::Result<GeneratedField, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        match value {
+                            "method" => Ok(GeneratedField::Method),
+                            "url" => Ok(GeneratedField::Url),
+                            "path" => Ok(GeneratedField::Path),
+                            "headers" => Ok(GeneratedField::Headers),
+                            "body" => Ok(GeneratedField::Body),
+                            "remoteAddr" | "remote_addr" => Ok(GeneratedField::RemoteAddr),
+                            "requestUri" | "request_uri" => Ok(GeneratedField::RequestUri),
+                            _ => Err(serde::de::Error::unknown_field(value, FIELDS)),
+                        }
+                    }
+                }
+                deserializer.deserialize_identifier(GeneratedVisitor)
+            }
+        }
+        struct GeneratedVisitor;
+        impl<'de> serde::de::Visitor<'de> for GeneratedVisitor {
+            type Value = HttpRequest;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                formatter.write_str("struct mozilla.mcpd.plugins.v1.HTTPRequest")
+            }
+
+            fn visit_map<V>(self, mut map_: V) -> std::result::Result<HttpRequest, V::Error>
+                where
+                    V: serde::de::MapAccess<'de>,
+            {
+                let mut method__ = None;
+                let mut url__ = None;
+                let mut path__ = None;
+                let mut headers__ = None;
+                let mut body__ = None;
+                let mut remote_addr__ = None;
+                let mut request_uri__ = None;
+                while let Some(k) = map_.next_key()? {
+                    match k {
+                        GeneratedField::Method => {
+                            if method__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("method"));
+                            }
+                            method__ = Some(map_.next_value()?);
+                        }
+                        GeneratedField::Url => {
+                            if url__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("url"));
+                            }
+                            url__ = Some(map_.next_value()?);
+                        }
+                        GeneratedField::Path => {
+                            if path__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("path"));
+                            }
+                            path__ = Some(map_.next_value()?);
+                        }
+                        GeneratedField::Headers => {
+                            if headers__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("headers"));
+                            }
+                            headers__ = Some(
+                                map_.next_value::<std::collections::HashMap<_, _>>()?
+                            );
+                        }
+                        GeneratedField::Body => {
+                            if body__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("body"));
+                            }
+                            body__ = 
+                                Some(map_.next_value::<::pbjson::private::BytesDeserialize<_>>()?.0)
+                            ;
+                        }
+                        GeneratedField::RemoteAddr => {
+                            if remote_addr__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("remoteAddr"));
+                            }
+                            remote_addr__ = Some(map_.next_value()?);
+                        }
+                        GeneratedField::RequestUri => {
+                            if request_uri__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("requestUri"));
+                            }
+                            request_uri__ = Some(map_.next_value()?);
+                        }
+                    }
+                }
+                Ok(HttpRequest {
+                    method: method__.unwrap_or_default(),
+                    url: url__.unwrap_or_default(),
+                    path: path__.unwrap_or_default(),
+                    headers: headers__.unwrap_or_default(),
+                    body: body__.unwrap_or_default(),
+                    remote_addr: remote_addr__.unwrap_or_default(),
+                    request_uri: request_uri__.unwrap_or_default(),
+                })
+            }
+        }
+        deserializer.deserialize_struct("mozilla.mcpd.plugins.v1.HTTPRequest", FIELDS, GeneratedVisitor)
+    }
+}
+impl serde::Serialize for HttpResponse {
+    #[allow(deprecated)]
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut len = 0;
+        if self.status_code != 0 {
+            len += 1;
+        }
+        if !self.headers.is_empty() {
+            len += 1;
+        }
+        if !self.body.is_empty() {
+            len += 1;
+        }
+        if self.r#continue {
+            len += 1;
+        }
+        if self.modified_request.is_some() {
+            len += 1;
+        }
+        let mut struct_ser = serializer.serialize_struct("mozilla.mcpd.plugins.v1.HTTPResponse", len)?;
+        if self.status_code != 0 {
+            struct_ser.serialize_field("statusCode", &self.status_code)?;
+        }
+        if !self.headers.is_empty() {
+            struct_ser.serialize_field("headers", &self.headers)?;
+        }
+        if !self.body.is_empty() {
+            #[allow(clippy::needless_borrow)]
+            struct_ser.serialize_field("body", pbjson::private::base64::encode(&self.body).as_str())?;
+        }
+        if self.r#continue {
+            struct_ser.serialize_field("continue", &self.r#continue)?;
+        }
+        if let Some(v) = self.modified_request.as_ref() {
+            struct_ser.serialize_field("modifiedRequest", v)?;
+        }
+        struct_ser.end()
+    }
+}
+impl<'de> serde::Deserialize<'de> for HttpResponse {
+    #[allow(deprecated)]
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        const FIELDS: &[&str] = &[
+            "status_code",
+            "statusCode",
+            "headers",
+            "body",
+            "continue",
+            "modified_request",
+            "modifiedRequest",
+        ];
+
+        #[allow(clippy::enum_variant_names)]
+        enum GeneratedField {
+            StatusCode,
+            Headers,
+            Body,
+            Continue,
+            ModifiedRequest,
+        }
+        impl<'de> serde::Deserialize<'de> for GeneratedField {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<GeneratedField, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct GeneratedVisitor;
+
+                impl<'de> serde::de::Visitor<'de> for GeneratedVisitor {
+                    type Value = GeneratedField;
+
+                    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        write!(formatter, "expected one of: {:?}", &FIELDS)
+                    }
+
+                    #[allow(unused_variables)]
+                    fn visit_str<E>(self, value: &str) -> std::result::Result<GeneratedField, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        match value {
+                            "statusCode" | "status_code" => Ok(GeneratedField::StatusCode),
+                            "headers" => Ok(GeneratedField::Headers),
+                            "body" => Ok(GeneratedField::Body),
+                            "continue" => Ok(GeneratedField::Continue),
+                            "modifiedRequest" | "modified_request" => Ok(GeneratedField::ModifiedRequest),
+                            _ => Err(serde::de::Error::unknown_field(value, FIELDS)),
+                        }
+                    }
+                }
+                deserializer.deserialize_identifier(GeneratedVisitor)
+            }
+        }
+        struct GeneratedVisitor;
+        impl<'de> serde::de::Visitor<'de> for GeneratedVisitor {
+            type Value = HttpResponse;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                formatter.write_str("struct mozilla.mcpd.plugins.v1.HTTPResponse")
+            }
+
+            fn visit_map<V>(self, mut map_: V) -> std::result::Result<HttpResponse, V::Error>
+                where
+                    V: serde::de::MapAccess<'de>,
+            {
+                let mut status_code__ = None;
+                let mut headers__ = None;
+                let mut body__ = None;
+                let mut r#continue__ = None;
+                let mut modified_request__ = None;
+                while let Some(k) = map_.next_key()? {
+                    match k {
+                        GeneratedField::StatusCode => {
+                            if status_code__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("statusCode"));
+                            }
+                            status_code__ = 
+                                Some(map_.next_value::<::pbjson::private::NumberDeserialize<_>>()?.0)
+                            ;
+                        }
+                        GeneratedField::Headers => {
+                            if headers__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("headers"));
+                            }
+                            headers__ = Some(
+                                map_.next_value::<std::collections::HashMap<_, _>>()?
+                            );
+                        }
+                        GeneratedField::Body => {
+                            if body__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("body"));
+                            }
+                            body__ = 
+                                Some(map_.next_value::<::pbjson::private::BytesDeserialize<_>>()?.0)
+                            ;
+                        }
+                        GeneratedField::Continue => {
+                            if r#continue__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("continue"));
+                            }
+                            r#continue__ = Some(map_.next_value()?);
+                        }
+                        GeneratedField::ModifiedRequest => {
+                            if modified_request__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("modifiedRequest"));
+                            }
+                            modified_request__ = map_.next_value()?;
+                        }
+                    }
+                }
+                Ok(HttpResponse {
+                    status_code: status_code__.unwrap_or_default(),
+                    headers: headers__.unwrap_or_default(),
+                    body: body__.unwrap_or_default(),
+                    r#continue: r#continue__.unwrap_or_default(),
+                    modified_request: modified_request__,
+                })
+            }
+        }
+        deserializer.deserialize_struct("mozilla.mcpd.plugins.v1.HTTPResponse", FIELDS, GeneratedVisitor)
+    }
+}
+impl serde::Serialize for Metadata {
+    #[allow(deprecated)]
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut len = 0;
+        if !self.name.is_empty() {
+            len += 1;
+        }
+        if !self.version.is_empty() {
+            len += 1;
+        }
+        if !self.description.is_empty() {
+            len += 1;
+        }
+        if !self.commit_hash.is_empty() {
+            len += 1;
+        }
+        if !self.build_date.is_empty() {
+            len += 1;
+        }
+        let mut struct_ser = serializer.serialize_struct("mozilla.mcpd.plugins.v1.Metadata", len)?;
+        if !self.name.is_empty() {
+            struct_ser.serialize_field("name", &self.name)?;
+        }
+        if !self.version.is_empty() {
+            struct_ser.serialize_field("version", &self.version)?;
+        }
+        if !self.description.is_empty() {
+            struct_ser.serialize_field("description", &self.description)?;
+        }
+        if !self.commit_hash.is_empty() {
+            struct_ser.serialize_field("commitHash", &self.commit_hash)?;
+        }
+        if !self.build_date.is_empty() {
+            struct_ser.serialize_field("buildDate", &self.build_date)?;
+        }
+        struct_ser.end()
+    }
+}
+impl<'de> serde::Deserialize<'de> for Metadata {
+    #[allow(deprecated)]
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        const FIELDS: &[&str] = &[
+            "name",
+            "version",
+            "description",
+            "commit_hash",
+            "commitHash",
+            "build_date",
+            "buildDate",
+        ];
+
+        #[allow(clippy::enum_variant_names)]
+        enum GeneratedField {
+            Name,
+            Version,
+            Description,
+            CommitHash,
+            BuildDate,
+        }
+        impl<'de> serde::Deserialize<'de> for GeneratedField {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<GeneratedField, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct GeneratedVisitor;
+
+                impl<'de> serde::de::Visitor<'de> for GeneratedVisitor {
+                    type Value = GeneratedField;
+
+                    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        write!(formatter, "expected one of: {:?}", &FIELDS)
+                    }
+
+                    #[allow(unused_variables)]
+                    fn visit_str<E>(self, value: &str) -> std::result::Result<GeneratedField, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        match value {
+                            "name" => Ok(GeneratedField::Name),
+                            "version" => Ok(GeneratedField::Version),
+                            "description" => Ok(GeneratedField::Description),
+                            "commitHash" | "commit_hash" => Ok(GeneratedField::CommitHash),
+                            "buildDate" | "build_date" => Ok(GeneratedField::BuildDate),
+                            _ => Err(serde::de::Error::unknown_field(value, FIELDS)),
+                        }
+                    }
+                }
+                deserializer.deserialize_identifier(GeneratedVisitor)
+            }
+        }
+        struct GeneratedVisitor;
+        impl<'de> serde::de::Visitor<'de> for GeneratedVisitor {
+            type Value = Metadata;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                formatter.write_str("struct mozilla.mcpd.plugins.v1.Metadata")
+            }
+
+            fn visit_map<V>(self, mut map_: V) -> std::result::Result<Metadata, V::Error>
+                where
+                    V: serde::de::MapAccess<'de>,
+            {
+                let mut name__ = None;
+                let mut version__ = None;
+                let mut description__ = None;
+                let mut commit_hash__ = None;
+                let mut build_date__ = None;
+                while let Some(k) = map_.next_key()? {
+                    match k {
+                        GeneratedField::Name => {
+                            if name__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("name"));
+                            }
+                            name__ = Some(map_.next_value()?);
+                        }
+                        GeneratedField::Version => {
+                            if version__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("version"));
+                            }
+                            version__ = Some(map_.next_value()?);
+                        }
+                        GeneratedField::Description => {
+                            if description__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("description"));
+                            }
+                            description__ = Some(map_.next_value()?);
+                        }
+                        GeneratedField::CommitHash => {
+                            if commit_hash__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("commitHash"));
+                            }
+                            commit_hash__ = Some(map_.next_value()?);
+                        }
+                        GeneratedField::BuildDate => {
+                            if build_date__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("buildDate"));
+                            }
+                            build_date__ = Some(map_.next_value()?);
+                        }
+                    }
+                }
+                Ok(Metadata {
+                    name: name__.unwrap_or_default(),
+                    version: version__.unwrap_or_default(),
+                    description: description__.unwrap_or_default(),
+                    commit_hash: commit_hash__.unwrap_or_default(),
+                    build_date: build_date__.unwrap_or_default(),
+                })
+            }
+        }
+        deserializer.deserialize_struct("mozilla.mcpd.plugins.v1.Metadata", FIELDS, GeneratedVisitor)
+    }
+}
+impl serde::Serialize for PluginConfig {
+    #[allow(deprecated)]
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut len = 0;
+        if self.telemetry.is_some() {
+            len += 1;
+        }
+        if !self.custom_config.is_empty() {
+            len += 1;
+        }
+        let mut struct_ser = serializer.serialize_struct("mozilla.mcpd.plugins.v1.PluginConfig", len)?;
+        if let Some(v) = self.telemetry.as_ref() {
+            struct_ser.serialize_field("telemetry", v)?;
+        }
+        if !self.custom_config.is_empty() {
+            struct_ser.serialize_field("customConfig", &self.custom_config)?;
+        }
+        struct_ser.end()
+    }
+}
+impl<'de> serde::Deserialize<'de> for PluginConfig {
+    #[allow(deprecated)]
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        const FIELDS: &[&str] = &[
+            "telemetry",
+            "custom_config",
+            "customConfig",
+        ];
+
+        #[allow(clippy::enum_variant_names)]
+        enum GeneratedField {
+            Telemetry,
+            CustomConfig,
+        }
+        impl<'de> serde::Deserialize<'de> for GeneratedField {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<GeneratedField, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct GeneratedVisitor;
+
+                impl<'de> serde::de::Visitor<'de> for GeneratedVisitor {
+                    type Value = GeneratedField;
+
+                    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        write!(formatter, "expected one of: {:?}", &FIELDS)
+                    }
+
+                    #[allow(unused_variables)]
+                    fn visit_str<E>(self, value: &str) -> std::result::Result<GeneratedField, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        match value {
+                            "telemetry" => Ok(GeneratedField::Telemetry),
+                            "customConfig" | "custom_config" => Ok(GeneratedField::CustomConfig),
+                            _ => Err(serde::de::Error::unknown_field(value, FIELDS)),
+                        }
+                    }
+                }
+                deserializer.deserialize_identifier(GeneratedVisitor)
+            }
+        }
+        struct GeneratedVisitor;
+        impl<'de> serde::de::Visitor<'de> for GeneratedVisitor {
+            type Value = PluginConfig;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                formatter.write_str("struct mozilla.mcpd.plugins.v1.PluginConfig")
+            }
+
+            fn visit_map<V>(self, mut map_: V) -> std::result::Result<PluginConfig, V::Error>
+                where
+                    V: serde::de::MapAccess<'de>,
+            {
+                let mut telemetry__ = None;
+                let mut custom_config__ = None;
+                while let Some(k) = map_.next_key()? {
+                    match k {
+                        GeneratedField::Telemetry => {
+                            if telemetry__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("telemetry"));
+                            }
+                            telemetry__ = map_.next_value()?;
+                        }
+                        GeneratedField::CustomConfig => {
+                            if custom_config__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("customConfig"));
+                            }
+                            custom_config__ = Some(
+                                map_.next_value::<std::collections::HashMap<_, _>>()?
+                            );
+                        }
+                    }
+                }
+                Ok(PluginConfig {
+                    telemetry: telemetry__,
+                    custom_config: custom_config__.unwrap_or_default(),
+                })
+            }
+        }
+        deserializer.deserialize_struct("mozilla.mcpd.plugins.v1.PluginConfig", FIELDS, GeneratedVisitor)
+    }
+}
+impl serde::Serialize for TelemetryConfig {
+    #[allow(deprecated)]
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut len = 0;
+        if !self.otlp_endpoint.is_empty() {
+            len += 1;
+        }
+        if !self.service_name.is_empty() {
+            len += 1;
+        }
+        if !self.environment.is_empty() {
+            len += 1;
+        }
+        if self.sample_ratio != 0. {
+            len += 1;
+        }
+        let mut struct_ser = serializer.serialize_struct("mozilla.mcpd.plugins.v1.TelemetryConfig", len)?;
+        if !self.otlp_endpoint.is_empty() {
+            struct_ser.serialize_field("otlpEndpoint", &self.otlp_endpoint)?;
+        }
+        if !self.service_name.is_empty() {
+            struct_ser.serialize_field("serviceName", &self.service_name)?;
+        }
+        if !self.environment.is_empty() {
+            struct_ser.serialize_field("environment", &self.environment)?;
+        }
+        if self.sample_ratio != 0. {
+            struct_ser.serialize_field("sampleRatio", &self.sample_ratio)?;
+        }
+        struct_ser.end()
+    }
+}
+impl<'de> serde::Deserialize<'de> for TelemetryConfig {
+    #[allow(deprecated)]
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        const FIELDS: &[&str] = &[
+            "otlp_endpoint",
+            "otlpEndpoint",
+            "service_name",
+            "serviceName",
+            "environment",
+            "sample_ratio",
+            "sampleRatio",
+        ];
+
+        #[allow(clippy::enum_variant_names)]
+        enum GeneratedField {
+            OtlpEndpoint,
+            ServiceName,
+            Environment,
+            SampleRatio,
+        }
+        impl<'de> serde::Deserialize<'de> for GeneratedField {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<GeneratedField, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct GeneratedVisitor;
+
+                impl<'de> serde::de::Visitor<'de> for GeneratedVisitor {
+                    type Value = GeneratedField;
+
+                    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        write!(formatter, "expected one of: {:?}", &FIELDS)
+                    }
+
+                    #[allow(unused_variables)]
+                    fn visit_str<E>(self, value: &str) -> std::result::Result<GeneratedField, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        match value {
+                            "otlpEndpoint" | "otlp_endpoint" => Ok(GeneratedField::OtlpEndpoint),
+                            "serviceName" | "service_name" => Ok(GeneratedField::ServiceName),
+                            "environment" => Ok(GeneratedField::Environment),
+                            "sampleRatio" | "sample_ratio" => Ok(GeneratedField::SampleRatio),
+                            _ => Err(serde::de::Error::unknown_field(value, FIELDS)),
+                        }
+                    }
+                }
+                deserializer.deserialize_identifier(GeneratedVisitor)
+            }
+        }
+        struct GeneratedVisitor;
+        impl<'de> serde::de::Visitor<'de> for GeneratedVisitor {
+            type Value = TelemetryConfig;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                formatter.write_str("struct mozilla.mcpd.plugins.v1.TelemetryConfig")
+            }
+
+            fn visit_map<V>(self, mut map_: V) -> std::result::Result<TelemetryConfig, V::Error>
+                where
+                    V: serde::de::MapAccess<'de>,
+            {
+                let mut otlp_endpoint__ = None;
+                let mut service_name__ = None;
+                let mut environment__ = None;
+                let mut sample_ratio__ = None;
+                while let Some(k) = map_.next_key()? {
+                    match k {
+                        GeneratedField::OtlpEndpoint => {
+                            if otlp_endpoint__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("otlpEndpoint"));
+                            }
+                            otlp_endpoint__ = Some(map_.next_value()?);
+                        }
+                        GeneratedField::ServiceName => {
+                            if service_name__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("serviceName"));
+                            }
+                            service_name__ = Some(map_.next_value()?);
+                        }
+                        GeneratedField::Environment => {
+                            if environment__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("environment"));
+                            }
+                            environment__ = Some(map_.next_value()?);
+                        }
+                        GeneratedField::SampleRatio => {
+                            if sample_ratio__.is_some() {
+                                return Err(serde::de::Error::duplicate_field("sampleRatio"));
+                            }
+                            sample_ratio__ = 
+                                Some(map_.next_value::<::pbjson::private::NumberDeserialize<_>>()?.0)
+                            ;
+                        }
+                    }
+                }
+                Ok(TelemetryConfig {
+                    otlp_endpoint: otlp_endpoint__.unwrap_or_default(),
+                    service_name: service_name__.unwrap_or_default(),
+                    environment: environment__.unwrap_or_default(),
+                    sample_ratio: sample_ratio__.unwrap_or_default(),
+                })
+            }
+        }
+        deserializer.deserialize_struct("mozilla.mcpd.plugins.v1.TelemetryConfig", FIELDS, GeneratedVisitor)
+    }
+}
@@ -0,0 +1,189 @@
+use crate::proto::plugin_server::Plugin as PluginService;
+use crate::version::{self, ProtocolVersion};
+use crate::Result;
+use std::io::Write;
+use tonic::Request;
+use tracing::warn;
+
+/// Version of the handshake line format, bumped on incompatible changes.
+const HANDSHAKE_VERSION: u32 = 1;
+
+/// Wire protocol spoken on the announced address.
+const WIRE_PROTOCOL: &str = "grpc";
+
+/// Details announced to the host on stdout once the plugin is listening.
+///
+/// The line has the form
+/// `HANDSHAKE_VERSION|PROTOCOL_VERSIONS|NETWORK|ADDRESS|grpc|NAME|VERSION`, e.g.
+/// `1|v1|unix|/tmp/my-plugin.sock|grpc|my-plugin|1.0.0`, where `PROTOCOL_VERSIONS` lists
+/// the served plugin protocol versions separated by commas. Any `%`, `|`, CR or LF within
+/// a field is percent-encoded.
+pub(crate) struct Handshake {
+    protocols: String,
+    name: String,
+    version: String,
+}
+
+impl Handshake {
+    /// Collects the plugin's name and version from `get_metadata`.
+    ///
+    /// If the plugin cannot report them, the fields are left empty rather than holding
+    /// back the handshake.
+    pub(crate) async fn from_metadata<S: PluginService>(
+        service: &S,
+        protocols: &[ProtocolVersion],
+    ) -> Self {
+        let protocols = version::join(protocols);
+        match service.get_metadata(Request::new(())).await {
+            Ok(metadata) => {
+                let metadata = metadata.into_inner();
+                Self {
+                    protocols,
+                    name: metadata.name,
+                    version: metadata.version,
+                }
+            }
+            Err(status) => {
+                warn!("Handshake without plugin metadata: {}", status.message());
+                Self {
+                    protocols,
+                    name: String::new(),
+                    version: String::new(),
+                }
+            }
+        }
+    }
+
+    /// Returns the handshake line for a listener on `network` at `address`.
+    pub(crate) fn line(&self, network: &str, address: &str) -> String {
+        [
+            &HANDSHAKE_VERSION.to_string(),
+            &self.protocols,
+            network,
+            address,
+            WIRE_PROTOCOL,
+            &self.name,
+            &self.version,
+        ]
+        .map(escape)
+        .join("|")
+    }
+
+    /// Writes the handshake line to stdout and flushes it.
+    pub(crate) fn announce(&self, network: &str, address: &str) -> Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{}", self.line(network, address))?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+/// Percent-encodes the characters that would break the line format.
+fn escape(field: &str) -> String {
+    field
+        .replace('%', "%25")
+        .replace('|', "%7C")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::{Plugin, PluginAdapter};
+    use crate::Metadata;
+    use tonic::{Response, Status};
+
+    fn handshake(name: &str, version: &str) -> Handshake {
+        Handshake {
+            protocols: "v1".to_string(),
+            name: name.to_string(),
+            version: version.to_string(),
+        }
+    }
+
+    struct Named;
+
+    #[tonic::async_trait]
+    impl Plugin for Named {
+        async fn get_metadata(
+            &self,
+            _request: Request<()>,
+        ) -> std::result::Result<Response<Metadata>, Status> {
+            Ok(Response::new(Metadata {
+                name: "my-plugin".to_string(),
+                version: "1.0.0".to_string(),
+                ..Default::default()
+            }))
+        }
+    }
+
+    struct Anonymous;
+
+    #[tonic::async_trait]
+    impl Plugin for Anonymous {
+        async fn get_metadata(
+            &self,
+            _request: Request<()>,
+        ) -> std::result::Result<Response<Metadata>, Status> {
+            Err(Status::internal("no metadata"))
+        }
+    }
+
+    #[test]
+    fn announces_a_unix_listener() {
+        assert_eq!(
+            handshake("my-plugin", "1.0.0").line("unix", "/tmp/my-plugin.sock"),
+            "1|v1|unix|/tmp/my-plugin.sock|grpc|my-plugin|1.0.0"
+        );
+    }
+
+    #[test]
+    fn announces_a_tcp_listener() {
+        assert_eq!(
+            handshake("my-plugin", "1.0.0").line("tcp", "127.0.0.1:50051"),
+            "1|v1|tcp|127.0.0.1:50051|grpc|my-plugin|1.0.0"
+        );
+        assert_eq!(
+            handshake("my-plugin", "1.0.0").line("tcp", "[::1]:50051"),
+            "1|v1|tcp|[::1]:50051|grpc|my-plugin|1.0.0"
+        );
+    }
+
+    #[test]
+    fn escapes_separators_and_line_breaks() {
+        let line = handshake("a|b", "1.0\r\n2.0").line("unix", "/tmp/100%|x\n.sock");
+        assert_eq!(
+            line,
+            "1|v1|unix|/tmp/100%25%7Cx%0A.sock|grpc|a%7Cb|1.0%0D%0A2.0"
+        );
+        assert_eq!(line.split('|').count(), 7);
+        assert!(!line.contains(['\r', '\n']));
+    }
+
+    #[test]
+    fn escapes_percent_signs_first() {
+        assert_eq!(escape("%7C"), "%257C");
+        assert_eq!(escape("|"), "%7C");
+    }
+
+    #[tokio::test]
+    async fn takes_name_and_version_from_metadata() {
+        let service = PluginAdapter::new(Named);
+        let handshake = Handshake::from_metadata(&service, &[ProtocolVersion::V1]).await;
+        assert_eq!(
+            handshake.line("unix", "/tmp/my-plugin.sock"),
+            "1|v1|unix|/tmp/my-plugin.sock|grpc|my-plugin|1.0.0"
+        );
+    }
+
+    #[tokio::test]
+    async fn leaves_fields_empty_without_metadata() {
+        let service = PluginAdapter::new(Anonymous);
+        let handshake = Handshake::from_metadata(&service, &[ProtocolVersion::V1]).await;
+        assert_eq!(
+            handshake.line("tcp", "127.0.0.1:50051"),
+            "1|v1|tcp|127.0.0.1:50051|grpc||"
+        );
+    }
+}
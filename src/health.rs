@@ -0,0 +1,204 @@
+use crate::proto::plugin_server::{self, Plugin as PluginService};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::{Request, Response, Status};
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+use tracing::info;
+
+/// Default interval between polls of `check_health` and `check_ready`.
+pub(crate) const DEFAULT_HEALTH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Name under which the overall server health is reported.
+const SERVER: &str = "";
+
+/// Keeps the `grpc.health.v1.Health` statuses in sync with the plugin's own checks.
+///
+/// The overall server status follows `check_health` (liveness); the plugin service's
+/// status follows `check_health` and `check_ready` together (readiness). A check that has
+/// not answered within `interval` counts as failed. Statuses are only updated when they
+/// change, so `Watch` streams see transitions rather than every poll.
+pub(crate) async fn sync<S: PluginService>(
+    service: Arc<S>,
+    mut reporter: HealthReporter,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut last = None;
+
+    loop {
+        ticker.tick().await;
+
+        let live = passes(service.check_health(Request::new(())), interval).await;
+        let ready = live && passes(service.check_ready(Request::new(())), interval).await;
+        if last == Some((live, ready)) {
+            continue;
+        }
+
+        if last.is_some() {
+            info!(
+                "Plugin health changed: live={}, ready={}",
+                serving_status(live),
+                serving_status(ready)
+            );
+        }
+        reporter
+            .set_service_status(SERVER, serving_status(live))
+            .await;
+        reporter
+            .set_service_status(plugin_server::SERVICE_NAME, serving_status(ready))
+            .await;
+        last = Some((live, ready));
+    }
+}
+
+/// Returns whether `check` succeeded within `timeout`.
+async fn passes<F>(check: F, timeout: Duration) -> bool
+where
+    F: Future<Output = std::result::Result<Response<()>, Status>>,
+{
+    matches!(tokio::time::timeout(timeout, check).await, Ok(Ok(_)))
+}
+
+fn serving_status(ok: bool) -> ServingStatus {
+    if ok {
+        ServingStatus::Serving
+    } else {
+        ServingStatus::NotServing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::{Plugin, PluginAdapter};
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tonic::body::BoxBody;
+    use tonic_health::pb::health_check_response::ServingStatus as Wire;
+    use tonic_health::pb::health_client::HealthClient;
+    use tonic_health::pb::HealthCheckRequest;
+    use tonic_health::server::health_reporter;
+    use tower::util::BoxCloneService;
+
+    const INTERVAL: Duration = Duration::from_millis(10);
+
+    /// Plugin whose checks pass or fail as its flags say.
+    #[derive(Clone, Default)]
+    struct Checks {
+        failing_health: Arc<AtomicBool>,
+        failing_ready: Arc<AtomicBool>,
+    }
+
+    #[tonic::async_trait]
+    impl Plugin for Checks {
+        async fn check_health(&self, _request: Request<()>) -> Result<Response<()>, Status> {
+            if self.failing_health.load(Ordering::SeqCst) {
+                return Err(Status::unavailable("unhealthy"));
+            }
+            Ok(Response::new(()))
+        }
+
+        async fn check_ready(&self, _request: Request<()>) -> Result<Response<()>, Status> {
+            if self.failing_ready.load(Ordering::SeqCst) {
+                return Err(Status::unavailable("not ready"));
+            }
+            Ok(Response::new(()))
+        }
+    }
+
+    fn request(service: &str) -> HealthCheckRequest {
+        HealthCheckRequest {
+            service: service.to_string(),
+        }
+    }
+
+    type Client =
+        HealthClient<BoxCloneService<http::Request<BoxBody>, http::Response<BoxBody>, Infallible>>;
+
+    /// Starts syncing `checks` and returns a client of the health service.
+    fn start(checks: &Checks) -> Client {
+        let (reporter, server) = health_reporter();
+        let service = Arc::new(PluginAdapter::new(checks.clone()));
+        tokio::spawn(sync(service, reporter, INTERVAL));
+        HealthClient::new(BoxCloneService::new(server))
+    }
+
+    /// Waits until `service` reports `expected`.
+    async fn wait_for(client: &mut Client, service: &str, expected: Wire) {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        loop {
+            let status = client
+                .check(request(service))
+                .await
+                .map(|response| response.into_inner().status());
+            if status.as_ref().ok() == Some(&expected) {
+                return;
+            }
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "{:?} reports {:?}, expected {:?}",
+                service,
+                status,
+                expected
+            );
+            tokio::time::sleep(INTERVAL).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn statuses_follow_the_plugin_checks() {
+        let checks = Checks::default();
+        let mut client = start(&checks);
+        let plugin = plugin_server::SERVICE_NAME;
+
+        wait_for(&mut client, SERVER, Wire::Serving).await;
+        wait_for(&mut client, plugin, Wire::Serving).await;
+
+        // Not ready: still live, but the plugin service stops serving.
+        checks.failing_ready.store(true, Ordering::SeqCst);
+        wait_for(&mut client, plugin, Wire::NotServing).await;
+        wait_for(&mut client, SERVER, Wire::Serving).await;
+
+        // Not live: both stop serving.
+        checks.failing_health.store(true, Ordering::SeqCst);
+        wait_for(&mut client, SERVER, Wire::NotServing).await;
+
+        checks.failing_health.store(false, Ordering::SeqCst);
+        checks.failing_ready.store(false, Ordering::SeqCst);
+        wait_for(&mut client, SERVER, Wire::Serving).await;
+        wait_for(&mut client, plugin, Wire::Serving).await;
+    }
+
+    #[tokio::test]
+    async fn watch_sees_the_transition() {
+        let checks = Checks::default();
+        let mut client = start(&checks);
+        let plugin = plugin_server::SERVICE_NAME;
+        wait_for(&mut client, plugin, Wire::Serving).await;
+
+        let mut stream = client.watch(request(plugin)).await.unwrap().into_inner();
+        let first = stream.message().await.unwrap().unwrap();
+        assert_eq!(first.status(), Wire::Serving);
+
+        checks.failing_ready.store(true, Ordering::SeqCst);
+        let next = tokio::time::timeout(Duration::from_secs(5), stream.message())
+            .await
+            .expect("no transition within 5s")
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.status(), Wire::NotServing);
+    }
+
+    #[tokio::test]
+    async fn a_check_slower_than_the_interval_fails() {
+        let slow = async {
+            tokio::time::sleep(INTERVAL * 10).await;
+            Ok(Response::new(()))
+        };
+        assert!(!passes(slow, INTERVAL).await);
+        assert!(passes(async { Ok(Response::new(())) }, INTERVAL).await);
+    }
+}
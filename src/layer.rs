@@ -0,0 +1,201 @@
+use crate::proto::plugin_server::SERVICE_NAME;
+use std::convert::Infallible;
+use std::task::{ready, Context, Poll};
+use tonic::body::BoxBody;
+use tonic::codegen::BoxFuture;
+use tonic::server::NamedService;
+use tonic::Status;
+use tower::util::BoxCloneService;
+use tower::{BoxError, Layer, Service};
+
+/// The plugin gRPC service in type-erased form, as seen by layers added with
+/// [`ServerBuilder::layer()`](crate::ServerBuilder::layer).
+pub type BoxPluginService =
+    BoxCloneService<http::Request<BoxBody>, http::Response<BoxBody>, Infallible>;
+
+/// A type-erased tower layer applied to the plugin service.
+pub(crate) type BoxPluginLayer = Box<dyn FnOnce(BoxPluginService) -> BoxPluginService + Send>;
+
+/// Erases the type of `layer` so layers of different types can be stacked.
+pub(crate) fn boxed<L, S, B>(layer: L) -> BoxPluginLayer
+where
+    L: Layer<BoxPluginService, Service = S> + Send + 'static,
+    S: Service<http::Request<BoxBody>, Response = http::Response<B>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+    B: http_body::Body<Data = bytes::Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    Box::new(move |service| BoxCloneService::new(ErrorAsStatus::new(layer.layer(service))))
+}
+
+/// Reports errors of a layered service to the caller as gRPC statuses.
+///
+/// The status is taken from the error's source chain, or is `Unknown` otherwise. Errors
+/// from `poll_ready` are held back and returned for the next call.
+struct ErrorAsStatus<S> {
+    inner: S,
+    ready_error: Option<BoxError>,
+}
+
+impl<S> ErrorAsStatus<S> {
+    fn new(inner: S) -> Self {
+        Self {
+            inner,
+            ready_error: None,
+        }
+    }
+}
+
+impl<S: Clone> Clone for ErrorAsStatus<S> {
+    fn clone(&self) -> Self {
+        Self::new(self.inner.clone())
+    }
+}
+
+impl<S, B> Service<http::Request<BoxBody>> for ErrorAsStatus<S>
+where
+    S: Service<http::Request<BoxBody>, Response = http::Response<B>>,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+    B: http_body::Body<Data = bytes::Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Infallible>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.ready_error.is_none() {
+            if let Err(err) = ready!(self.inner.poll_ready(cx)) {
+                self.ready_error = Some(err.into());
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        if let Some(err) = self.ready_error.take() {
+            return Box::pin(std::future::ready(Ok(Status::from_error(err).into_http())));
+        }
+
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            Ok(match future.await {
+                Ok(response) => response.map(tonic::body::boxed),
+                Err(err) => Status::from_error(err.into()).into_http(),
+            })
+        })
+    }
+}
+
+/// Gives a layered plugin service the gRPC service name the router expects.
+#[derive(Clone)]
+pub(crate) struct NamedPluginService(pub(crate) BoxPluginService);
+
+impl Service<http::Request<BoxBody>> for NamedPluginService {
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = <BoxPluginService as Service<http::Request<BoxBody>>>::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        self.0.call(request)
+    }
+}
+
+impl NamedService for NamedPluginService {
+    const NAME: &'static str = SERVICE_NAME;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+    use tower::ServiceExt;
+
+    /// A plugin service answering every call with an empty OK response.
+    fn ok_service() -> BoxPluginService {
+        BoxCloneService::new(tower::service_fn(|_request| async {
+            Ok::<_, Infallible>(http::Response::new(tonic::body::empty_body()))
+        }))
+    }
+
+    /// Wraps the plugin service in a service failing every call with `error`.
+    fn failing<F>(error: F) -> BoxPluginLayer
+    where
+        F: Fn() -> BoxError + Clone + Send + Sync + 'static,
+    {
+        boxed(tower::layer::layer_fn(move |_inner: BoxPluginService| {
+            let error = error.clone();
+            tower::service_fn(move |_request: http::Request<BoxBody>| {
+                let error = error();
+                async move { Err::<http::Response<BoxBody>, _>(error) }
+            })
+        }))
+    }
+
+    /// Returns the `grpc-status` code of `response`.
+    fn code(response: &http::Response<BoxBody>) -> Code {
+        let status = response.headers()["grpc-status"].to_str().unwrap();
+        Code::from_i32(status.parse().unwrap())
+    }
+
+    async fn call(layer: BoxPluginLayer) -> http::Response<BoxBody> {
+        layer(ok_service())
+            .oneshot(http::Request::new(tonic::body::empty_body()))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn passes_responses_through() {
+        let layer = boxed(tower::layer::layer_fn(|inner: BoxPluginService| inner));
+        let response = call(layer).await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert!(response.headers().get("grpc-status").is_none());
+    }
+
+    #[tokio::test]
+    async fn maps_a_status_error_to_its_code() {
+        let response = call(failing(|| Status::permission_denied("no").into())).await;
+        assert_eq!(code(&response), Code::PermissionDenied);
+        assert_eq!(response.headers()["grpc-message"], "no");
+    }
+
+    #[tokio::test]
+    async fn maps_other_errors_to_unknown() {
+        let response = call(failing(|| "layer failed".into())).await;
+        assert_eq!(code(&response), Code::Unknown);
+    }
+
+    #[tokio::test]
+    async fn returns_a_readiness_error_on_the_next_call() {
+        /// Never becomes ready.
+        #[derive(Clone)]
+        struct Unavailable;
+
+        impl Service<http::Request<BoxBody>> for Unavailable {
+            type Response = http::Response<BoxBody>;
+            type Error = BoxError;
+            type Future = BoxFuture<Self::Response, BoxError>;
+
+            fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+                Poll::Ready(Err(Status::unavailable("overloaded").into()))
+            }
+
+            fn call(&mut self, _request: http::Request<BoxBody>) -> Self::Future {
+                unreachable!("called without being ready")
+            }
+        }
+
+        let layer = boxed(tower::layer::layer_fn(|_inner: BoxPluginService| {
+            Unavailable
+        }));
+        let response = call(layer).await;
+        assert_eq!(code(&response), Code::Unavailable);
+    }
+}
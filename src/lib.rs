@@ -11,8 +11,24 @@
 //! - **Async/await support**: Built on Tokio and Tonic for high-performance async I/O
 //! - **Automatic server setup**: [`serve()`] function handles all boilerplate
 //! - **Cross-platform**: Unix sockets (Linux/macOS) and TCP support
+//! - **Socket activation**: Serve on listening sockets inherited via `LISTEN_FDS` or `--network fd`
 //! - **Type-safe**: Protocol buffers for serialization
+//! - **Serde**: Protobuf JSON mapping for all messages with the `serde` feature
+//! - **Conformance suite**: Check a plugin against the protocol contract from its own tests (`testing` feature)
+//! - **Property testing**: `proptest` strategies for every proto message (`proptest` feature)
+//! - **Record and replay**: Capture traffic to JSONL and replay it against a plugin (`record` feature)
+//! - **Protocol versions**: Version-namespaced generated code and negotiation via [`ProtocolVersion`]
 //! - **Graceful shutdown**: SIGINT/SIGTERM handling with cleanup
+//! - **Host watchdog**: Optionally exit when mcpd goes away
+//! - **Handshake**: Optional machine-readable ready line on stdout for hosts
+//! - **Standard health checks**: `grpc.health.v1.Health` kept in sync with the plugin's checks
+//! - **Header-only plugins**: Skip body transfer with [`Plugin::needs_body()`] and unchanged-body omission
+//! - **Message limits and compression**: Configurable gRPC message sizes and gzip/zstd via [`ServerBuilder`]
+//! - **Deadlines**: Per-RPC timeouts with a fail-open or fail-closed policy via [`ServerBuilder`]
+//! - **Load shedding**: Bounded in-flight request handling with queueing or shedding
+//! - **Composable middleware**: Tower layers and plugin-level [`Middleware`]
+//! - **Plugin chains**: Run several plugins as one with [`PluginChain`]
+//! - **Routing**: Per-route handling and bypass lists with [`RequestRouter`]
 //!
 //! ## Quick Start
 //!
@@ -138,7 +154,7 @@
 //!         Ok(Response::new(HttpResponse {
 //!             r#continue: false,
 //!             status_code: 401,
-//!             body: b"Unauthorized".to_vec(),
+//!             body: "Unauthorized".into(),
 //!             ..Default::default()
 //!         }))
 //!     }
@@ -149,19 +165,80 @@
 #[allow(clippy::all)]
 #[allow(missing_docs)]
 pub mod proto {
-    include!("generated/mozilla.mcpd.plugins.v1.rs");
+    /// Plugin protocol v1 (`mozilla.mcpd.plugins.v1`).
+    ///
+    /// Always compiled, since the `Plugin` trait is written against its types; it is only
+    /// served with the `proto-v1` feature.
+    pub mod v1 {
+        include!("generated/mozilla.mcpd.plugins.v1.rs");
+
+        // Serde impls following the protobuf JSON mapping.
+        #[cfg(feature = "serde")]
+        include!("generated/mozilla.mcpd.plugins.v1.serde.rs");
+
+        /// Encoded file descriptor set of `plugin.proto` and its imports, served by reflection.
+        #[cfg(feature = "reflection")]
+        pub const FILE_DESCRIPTOR_SET: &[u8] =
+            include_bytes!("generated/mozilla.mcpd.plugins.v1.bin");
+    }
+
+    // The `Plugin` trait is written against v1, which stays available at the root.
+    pub use v1::*;
 }
 
+#[cfg(not(any(feature = "proto-v1")))]
+compile_error!("enable at least one plugin protocol version to serve, e.g. the `proto-v1` feature");
+
+#[cfg(unix)]
+mod activation;
+mod body;
+mod chain;
+mod codec;
+mod concurrency;
 mod constants;
 mod error;
+mod handshake;
+mod health;
+mod layer;
+mod middleware;
+mod peer;
 mod plugin;
+#[cfg(feature = "record")]
+mod record;
+mod router;
 mod server;
+mod socket;
+mod stats;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod timeout;
+mod version;
+mod watchdog;
 
 // Re-export public API.
+pub use body::{BODYLESS_FLOWS_HEADER, BODY_UNCHANGED_HEADER, OMIT_UNCHANGED_BODY_HEADER};
+pub use chain::PluginChain;
+pub use codec::Compression;
+pub use concurrency::OverloadPolicy;
 pub use constants::{FLOW_REQUEST, FLOW_RESPONSE};
 pub use error::{PluginError, Result};
+pub use layer::BoxPluginService;
+pub use middleware::{Middleware, Next};
+pub use peer::PeerCredentials;
 pub use plugin::{Plugin, PluginAdapter};
 pub use proto::{
     Capabilities, Flow, HttpRequest, HttpResponse, Metadata, PluginConfig, TelemetryConfig,
 };
-pub use server::serve;
+#[cfg(feature = "record")]
+pub use record::{
+    Mismatch, RecordedCall, RecordedInput, RecordedOutput, Recorder, Replay, ReplayReport,
+};
+pub use router::{PathParams, RequestRouter, RouteHandler};
+pub use server::{serve, ServerBuilder};
+#[cfg(unix)]
+pub use socket::connect_unix;
+pub use stats::AdapterStats;
+pub use timeout::TimeoutPolicy;
+pub use version::{ProtocolVersion, PROTOCOL_VERSIONS_HEADER, PROTOCOL_VERSION_HEADER};
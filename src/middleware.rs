@@ -0,0 +1,257 @@
+use crate::plugin::Plugin;
+use crate::proto::{HttpRequest, HttpResponse};
+use tonic::{Request, Response, Status};
+
+/// Plugin-level middleware that wraps [`Plugin::handle_request()`] and
+/// [`Plugin::handle_response()`].
+///
+/// Middleware is registered with [`ServerBuilder::middleware()`](crate::ServerBuilder::middleware)
+/// or [`PluginAdapter::middleware()`](crate::PluginAdapter::middleware) and runs in the order
+/// it was added, the first one being outermost. Each method receives a [`Next`] handle to call
+/// the rest of the chain; not calling it short-circuits the plugin. Both methods default to
+/// passing straight through, so implementations only override what they need.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use mcpd_plugins_sdk::{HttpRequest, HttpResponse, Middleware, Next};
+/// use tonic::{Request, Response, Status};
+///
+/// struct LogRequests;
+///
+/// #[tonic::async_trait]
+/// impl Middleware for LogRequests {
+///     async fn handle_request(
+///         &self,
+///         request: Request<HttpRequest>,
+///         next: Next<'_>,
+///     ) -> Result<Response<HttpResponse>, Status> {
+///         let path = request.get_ref().path.clone();
+///         let response = next.handle_request(request).await;
+///         tracing::info!("Handled {} (ok: {})", path, response.is_ok());
+///         response
+///     }
+/// }
+/// ```
+#[tonic::async_trait]
+pub trait Middleware: Send + Sync + 'static {
+    /// Wraps the handling of an incoming HTTP request.
+    async fn handle_request(
+        &self,
+        request: Request<HttpRequest>,
+        next: Next<'_>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        next.handle_request(request).await
+    }
+
+    /// Wraps the handling of an outgoing HTTP response.
+    async fn handle_response(
+        &self,
+        response: Request<HttpResponse>,
+        next: Next<'_>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        next.handle_response(response).await
+    }
+}
+
+/// The remainder of a middleware chain, ending in the plugin itself.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    plugin: &'a dyn Plugin,
+    middleware: &'a [Box<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(plugin: &'a dyn Plugin, middleware: &'a [Box<dyn Middleware>]) -> Self {
+        Self { plugin, middleware }
+    }
+
+    /// Passes the request to the next middleware, or to the plugin if none remain.
+    pub async fn handle_request(
+        self,
+        request: Request<HttpRequest>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        match self.middleware.split_first() {
+            Some((current, rest)) => {
+                current
+                    .handle_request(request, Next::new(self.plugin, rest))
+                    .await
+            }
+            None => self.plugin.handle_request(request).await,
+        }
+    }
+
+    /// Passes the response to the next middleware, or to the plugin if none remain.
+    pub async fn handle_response(
+        self,
+        response: Request<HttpResponse>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        match self.middleware.split_first() {
+            Some((current, rest)) => {
+                current
+                    .handle_response(response, Next::new(self.plugin, rest))
+                    .await
+            }
+            None => self.plugin.handle_response(response).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    /// Logs entering and leaving each call under its name.
+    struct Logging {
+        name: &'static str,
+        log: Log,
+    }
+
+    #[tonic::async_trait]
+    impl Middleware for Logging {
+        async fn handle_request(
+            &self,
+            request: Request<HttpRequest>,
+            next: Next<'_>,
+        ) -> Result<Response<HttpResponse>, Status> {
+            self.log.lock().unwrap().push(format!("{} in", self.name));
+            let response = next.handle_request(request).await;
+            self.log.lock().unwrap().push(format!("{} out", self.name));
+            response
+        }
+
+        async fn handle_response(
+            &self,
+            response: Request<HttpResponse>,
+            next: Next<'_>,
+        ) -> Result<Response<HttpResponse>, Status> {
+            self.log.lock().unwrap().push(format!("{} in", self.name));
+            let response = next.handle_response(response).await;
+            self.log.lock().unwrap().push(format!("{} out", self.name));
+            response
+        }
+    }
+
+    /// Rejects every request without calling the rest of the chain.
+    struct Reject;
+
+    #[tonic::async_trait]
+    impl Middleware for Reject {
+        async fn handle_request(
+            &self,
+            _request: Request<HttpRequest>,
+            _next: Next<'_>,
+        ) -> Result<Response<HttpResponse>, Status> {
+            Ok(Response::new(HttpResponse {
+                r#continue: false,
+                status_code: 403,
+                ..Default::default()
+            }))
+        }
+    }
+
+    /// Logs each call it handles.
+    struct Recorded {
+        log: Log,
+    }
+
+    #[tonic::async_trait]
+    impl Plugin for Recorded {
+        async fn handle_request(
+            &self,
+            _request: Request<HttpRequest>,
+        ) -> Result<Response<HttpResponse>, Status> {
+            self.log.lock().unwrap().push("plugin".to_string());
+            Ok(Response::new(HttpResponse {
+                r#continue: true,
+                ..Default::default()
+            }))
+        }
+
+        async fn handle_response(
+            &self,
+            response: Request<HttpResponse>,
+        ) -> Result<Response<HttpResponse>, Status> {
+            self.log.lock().unwrap().push("plugin".to_string());
+            Ok(Response::new(response.into_inner()))
+        }
+    }
+
+    fn logging(names: &[&'static str], log: &Log) -> Vec<Box<dyn Middleware>> {
+        names
+            .iter()
+            .map(|&name| {
+                Box::new(Logging {
+                    name,
+                    log: log.clone(),
+                }) as Box<dyn Middleware>
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn runs_in_order_added_first_outermost() {
+        let log = Log::default();
+        let plugin = Recorded { log: log.clone() };
+        let middleware = logging(&["first", "second"], &log);
+        let expected = ["first in", "second in", "plugin", "second out", "first out"];
+
+        Next::new(&plugin, &middleware)
+            .handle_request(Request::new(HttpRequest::default()))
+            .await
+            .unwrap();
+        assert_eq!(*log.lock().unwrap(), expected);
+
+        log.lock().unwrap().clear();
+        Next::new(&plugin, &middleware)
+            .handle_response(Request::new(HttpResponse::default()))
+            .await
+            .unwrap();
+        assert_eq!(*log.lock().unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn not_calling_next_skips_the_rest_of_the_chain() {
+        let log = Log::default();
+        let plugin = Recorded { log: log.clone() };
+        let mut middleware = logging(&["outer"], &log);
+        middleware.push(Box::new(Reject));
+        middleware.extend(logging(&["inner"], &log));
+
+        let response = Next::new(&plugin, &middleware)
+            .handle_request(Request::new(HttpRequest::default()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!response.r#continue);
+        assert_eq!(response.status_code, 403);
+        assert_eq!(*log.lock().unwrap(), ["outer in", "outer out"]);
+    }
+
+    #[tokio::test]
+    async fn default_methods_pass_through() {
+        struct Nothing;
+
+        #[tonic::async_trait]
+        impl Middleware for Nothing {}
+
+        let log = Log::default();
+        let plugin = Recorded { log: log.clone() };
+        let middleware: Vec<Box<dyn Middleware>> = vec![Box::new(Nothing)];
+
+        let response = HttpResponse {
+            r#continue: true,
+            status_code: 200,
+            ..Default::default()
+        };
+        let result = Next::new(&plugin, &middleware)
+            .handle_response(Request::new(response.clone()))
+            .await
+            .unwrap();
+        assert_eq!(result.into_inner(), response);
+        assert_eq!(*log.lock().unwrap(), ["plugin"]);
+    }
+}
@@ -0,0 +1,181 @@
+#[cfg(unix)]
+use tokio::net::UnixStream;
+#[cfg(unix)]
+use tracing::{debug, warn};
+
+/// Allowlist of local peers that may connect to a plugin's unix socket.
+///
+/// When set via [`ServerBuilder::peer_credentials()`](crate::ServerBuilder::peer_credentials)
+/// (or `--verify-peer`), the credentials of every accepted connection are read from the
+/// kernel (`SO_PEERCRED` on Linux) and the connection is closed before any RPC runs unless
+/// its user id, group id or process id is on the allowlist. An empty allowlist admits the
+/// plugin's own effective user only.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use mcpd_plugins_sdk::{PeerCredentials, Plugin, ServerBuilder};
+///
+/// struct MyPlugin;
+///
+/// #[tonic::async_trait]
+/// impl Plugin for MyPlugin {}
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     // Only the process that launched the plugin (mcpd) may connect.
+///     ServerBuilder::new(MyPlugin)
+///         .peer_credentials(PeerCredentials::new().allow_parent())
+///         .serve(None)
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PeerCredentials {
+    uids: Vec<u32>,
+    gids: Vec<u32>,
+    pids: Vec<i32>,
+}
+
+impl PeerCredentials {
+    /// Creates an empty allowlist, which admits the plugin's own effective user.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Admits connections from processes running as `uid`.
+    pub fn allow_uid(mut self, uid: u32) -> Self {
+        self.uids.push(uid);
+        self
+    }
+
+    /// Admits connections from processes whose primary group is `gid`.
+    pub fn allow_gid(mut self, gid: u32) -> Self {
+        self.gids.push(gid);
+        self
+    }
+
+    /// Admits connections from the process `pid`.
+    pub fn allow_pid(mut self, pid: i32) -> Self {
+        self.pids.push(pid);
+        self
+    }
+
+    /// Admits connections from the process that started the plugin, typically mcpd.
+    #[cfg(unix)]
+    pub fn allow_parent(self) -> Self {
+        self.allow_pid(std::os::unix::process::parent_id() as i32)
+    }
+
+    /// Returns the allowlist with the plugin's own effective user filled in if it is empty.
+    #[cfg(unix)]
+    pub(crate) fn or_current_user(self) -> Self {
+        if self.uids.is_empty() && self.gids.is_empty() && self.pids.is_empty() {
+            // SAFETY: geteuid has no preconditions and cannot fail.
+            self.allow_uid(unsafe { libc::geteuid() })
+        } else {
+            self
+        }
+    }
+
+    /// Returns true if the peer of `stream` is on the allowlist, logging rejections.
+    #[cfg(unix)]
+    pub(crate) fn admits(&self, stream: &UnixStream) -> bool {
+        let cred = match stream.peer_cred() {
+            Ok(cred) => cred,
+            Err(err) => {
+                warn!(
+                    "Rejecting connection: cannot read peer credentials: {}",
+                    err
+                );
+                return false;
+            }
+        };
+
+        let admitted = self.uids.contains(&cred.uid())
+            || self.gids.contains(&cred.gid())
+            || cred.pid().is_some_and(|pid| self.pids.contains(&pid));
+
+        if admitted {
+            debug!(
+                "Accepted connection from uid={} gid={} pid={:?}",
+                cred.uid(),
+                cred.gid(),
+                cred.pid()
+            );
+        } else {
+            warn!(
+                "Rejecting connection from uid={} gid={} pid={:?}: not on the peer allowlist",
+                cred.uid(),
+                cred.gid(),
+                cred.pid()
+            );
+        }
+        admitted
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn pair() -> UnixStream {
+        let (stream, _peer) = UnixStream::pair().unwrap();
+        stream
+    }
+
+    fn own_ids() -> (u32, u32) {
+        // SAFETY: geteuid and getegid have no preconditions and cannot fail.
+        unsafe { (libc::geteuid(), libc::getegid()) }
+    }
+
+    #[tokio::test]
+    async fn admits_the_own_user_by_default() {
+        let (uid, _) = own_ids();
+        let credentials = PeerCredentials::new().or_current_user();
+        assert_eq!(credentials, PeerCredentials::new().allow_uid(uid));
+        assert!(credentials.admits(&pair()));
+    }
+
+    #[tokio::test]
+    async fn keeps_an_explicit_allowlist() {
+        let credentials = PeerCredentials::new().allow_pid(1).or_current_user();
+        assert_eq!(credentials, PeerCredentials::new().allow_pid(1));
+    }
+
+    #[tokio::test]
+    async fn rejects_peers_off_the_allowlist() {
+        let (uid, gid) = own_ids();
+        let stream = pair();
+        for credentials in [
+            PeerCredentials::new().allow_uid(uid.wrapping_add(1)),
+            PeerCredentials::new().allow_gid(gid.wrapping_add(1)),
+            PeerCredentials::new().allow_pid(i32::MAX),
+            PeerCredentials::new()
+                .allow_uid(uid.wrapping_add(1))
+                .allow_gid(gid.wrapping_add(1))
+                .allow_pid(i32::MAX),
+        ] {
+            assert!(!credentials.admits(&stream), "{:?}", credentials);
+        }
+    }
+
+    #[tokio::test]
+    async fn admits_peers_on_the_allowlist() {
+        let (uid, gid) = own_ids();
+        let stream = pair();
+        let mut allowlists = vec![
+            PeerCredentials::new().allow_uid(uid),
+            PeerCredentials::new().allow_gid(gid),
+        ];
+        // Not every platform reports the peer's pid.
+        if let Some(pid) = stream.peer_cred().unwrap().pid() {
+            assert_eq!(pid, std::process::id() as i32);
+            allowlists.push(PeerCredentials::new().allow_pid(pid));
+        }
+        for credentials in allowlists {
+            assert!(credentials.admits(&stream), "{:?}", credentials);
+        }
+    }
+}
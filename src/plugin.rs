@@ -1,8 +1,22 @@
+use crate::body;
+use crate::codec;
+use crate::concurrency::{ConcurrencyLimit, OverloadPolicy};
+use crate::middleware::{Middleware, Next};
 use crate::proto::{
-    plugin_server::Plugin as PluginService, Capabilities, HttpRequest, HttpResponse, Metadata,
-    PluginConfig,
+    plugin_server::Plugin as PluginService, Capabilities, Flow, HttpRequest, HttpResponse,
+    Metadata, PluginConfig,
 };
+#[cfg(feature = "record")]
+use crate::record::{RecordedInput, Recorder};
+use crate::stats::AdapterStats;
+use crate::timeout::{self, TimeoutPolicy};
+use crate::version::{self, ProtocolVersion};
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
 use tonic::{Request, Response, Status};
+use tracing::warn;
 
 /// Main Plugin trait that all plugins must implement.
 ///
@@ -96,12 +110,36 @@ pub trait Plugin: Send + Sync + 'static {
         Ok(Response::new(()))
     }
 
+    /// Returns whether the plugin reads the message body in `flow`.
+    ///
+    /// Plugins that only look at the method, path and headers return `false`. `GetCapabilities`
+    /// then lists the flow in [`BODYLESS_FLOWS_HEADER`](crate::BODYLESS_FLOWS_HEADER), and
+    /// the host may send its messages with an empty body, so the plugin must not rely on it.
+    /// Middleware registered on the adapter sees the same empty body.
+    ///
+    /// Independently of this hint, when the host sends
+    /// [`OMIT_UNCHANGED_BODY_HEADER`](crate::OMIT_UNCHANGED_BODY_HEADER), a continuing result
+    /// whose body is the buffer the plugin received (the `modified_request` body for
+    /// requests) is returned with an empty body and
+    /// [`BODY_UNCHANGED_HEADER`](crate::BODY_UNCHANGED_HEADER), telling the host to keep its
+    /// original body. This covers the default [`handle_response()`](Plugin::handle_response)
+    /// and any handler that passes the body through or clones it; a body that was rebuilt,
+    /// even with the same content, is sent in full.
+    ///
+    /// Defaults to `true` for both flows.
+    fn needs_body(&self, _flow: Flow) -> bool {
+        true
+    }
+
     /// Handles incoming HTTP requests.
     ///
     /// Override this method to process, transform, or reject HTTP requests.
     /// Return `HttpResponse { continue_: true, .. }` to pass the request to the next handler.
     /// Return `HttpResponse { continue_: false, status_code, .. }` to short-circuit and
     /// return a response directly to the client.
+    ///
+    /// Bodies are [`Bytes`](bytes::Bytes): returning the request in `modified_request` with
+    /// only its headers changed hands back the same body buffer without copying it.
     async fn handle_request(
         &self,
         request: Request<HttpRequest>,
@@ -135,56 +173,351 @@ pub trait Plugin: Send + Sync + 'static {
 /// Adapter that implements the generated gRPC service trait using our Plugin trait.
 ///
 /// This bridges between the tonic-generated PluginService trait and our custom Plugin trait.
+/// It also enforces per-RPC deadlines: the shorter of the configured
+/// [`rpc_timeout`](Self::rpc_timeout) and the caller's `grpc-timeout` header applies (the
+/// latter less a margin, so the deadline expires here before tonic cancels the call), and
+/// the [`TimeoutPolicy`] decides what `HandleRequest`/`HandleResponse` return on expiry.
+/// Optionally it bounds how many of those two RPCs run at once, see
+/// [`max_in_flight`](Self::max_in_flight), and runs any registered [`Middleware`] around
+/// request and response handling, and with the `record` feature can append those calls to
+/// a [`Recorder`](crate::Recorder). `GetMetadata` responses also carry the protocol version
+/// negotiation headers, see [`ProtocolVersion`].
 pub struct PluginAdapter<P: Plugin> {
     plugin: P,
+    middleware: Vec<Box<dyn Middleware>>,
+    rpc_timeout: Option<Duration>,
+    timeout_policy: TimeoutPolicy,
+    concurrency_limit: Option<ConcurrencyLimit>,
+    stats: Arc<AdapterStats>,
+    protocol_versions: Vec<ProtocolVersion>,
+    max_encoding_message_size: Option<usize>,
+    #[cfg(feature = "record")]
+    recorder: Option<Recorder>,
 }
 
 impl<P: Plugin> PluginAdapter<P> {
     pub fn new(plugin: P) -> Self {
-        Self { plugin }
+        Self {
+            plugin,
+            middleware: Vec::new(),
+            rpc_timeout: None,
+            timeout_policy: TimeoutPolicy::default(),
+            concurrency_limit: None,
+            stats: Arc::new(AdapterStats::default()),
+            protocol_versions: ProtocolVersion::ALL.to_vec(),
+            max_encoding_message_size: None,
+            #[cfg(feature = "record")]
+            recorder: None,
+        }
+    }
+
+    /// Sets the maximum time any single RPC may take.
+    pub fn rpc_timeout(mut self, timeout: Duration) -> Self {
+        self.rpc_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets what request and response handlers return when they exceed their deadline.
+    pub fn timeout_policy(mut self, policy: TimeoutPolicy) -> Self {
+        self.timeout_policy = policy;
+        self
+    }
+
+    /// Limits how many `HandleRequest`/`HandleResponse` calls may run at once.
+    ///
+    /// Further calls are queued or shed according to `policy`, and `CheckReady` reports
+    /// not-ready while every slot is taken so the host can back off.
+    pub fn max_in_flight(mut self, max_in_flight: NonZeroUsize, policy: OverloadPolicy) -> Self {
+        self.concurrency_limit = Some(ConcurrencyLimit::new(max_in_flight, policy));
+        self
+    }
+
+    /// Adds middleware around `handle_request` and `handle_response`.
+    ///
+    /// Middleware runs in the order it is added, the first one being outermost.
+    pub fn middleware<M: Middleware>(mut self, middleware: M) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Appends every `HandleRequest`/`HandleResponse` call to a recording.
+    #[cfg(feature = "record")]
+    pub fn record(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Sets the protocol versions reported as served in `GetMetadata` responses.
+    pub(crate) fn protocol_versions(mut self, versions: Vec<ProtocolVersion>) -> Self {
+        self.protocol_versions = versions;
+        self
+    }
+
+    /// Sets the encoding limit that `HandleRequest`/`HandleResponse` results are checked
+    /// against, so an oversized result fails with a logged, descriptive status.
+    pub(crate) fn max_encoding_message_size(mut self, limit: Option<usize>) -> Self {
+        self.max_encoding_message_size = limit;
+        self
+    }
+
+    /// Returns the counters for this adapter.
+    pub fn stats(&self) -> Arc<AdapterStats> {
+        self.stats.clone()
+    }
+
+    /// Returns the entry point of the middleware chain.
+    fn next(&self) -> Next<'_> {
+        Next::new(&self.plugin, &self.middleware)
+    }
+
+    /// Runs a request or response handler within the in-flight limit, if one is set.
+    async fn limited<F, T>(&self, rpc: &str, handler: F) -> Result<Response<T>, Status>
+    where
+        F: Future<Output = Result<Response<T>, Status>>,
+    {
+        let Some(limit) = &self.concurrency_limit else {
+            return handler.await;
+        };
+
+        let Some(_permit) = limit.acquire().await else {
+            self.stats.record_shed();
+            warn!(
+                "{} rejected, {} calls already in flight ({})",
+                rpc,
+                limit.max_in_flight(),
+                limit.policy().as_str()
+            );
+            return Err(Status::resource_exhausted(format!(
+                "plugin is handling the maximum of {} concurrent calls",
+                limit.max_in_flight()
+            )));
+        };
+
+        handler.await
+    }
+
+    /// Handles a request within the deadline and in-flight limit.
+    async fn process_request(
+        &self,
+        request: Request<HttpRequest>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        let timeout = timeout::effective_timeout(self.rpc_timeout, request.metadata());
+        let handler = self.limited("HandleRequest", self.next().handle_request(request));
+        match timeout::within(timeout, handler).await {
+            Ok(result) => result,
+            Err(_) if self.timeout_policy == TimeoutPolicy::FailOpen => {
+                let fallback = HttpResponse {
+                    r#continue: true,
+                    ..Default::default()
+                };
+                Ok(self.fail_open("HandleRequest", timeout, fallback))
+            }
+            Err(_) => Err(self.fail_closed("HandleRequest", timeout)),
+        }
+    }
+
+    /// Handles a response within the deadline and in-flight limit.
+    async fn process_response(
+        &self,
+        response: Request<HttpResponse>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        let timeout = timeout::effective_timeout(self.rpc_timeout, response.metadata());
+
+        // Failing open must hand back the response as received, so keep a copy. The body
+        // is shared with the original rather than copied.
+        let original = (timeout.is_some() && self.timeout_policy == TimeoutPolicy::FailOpen)
+            .then(|| response.get_ref().clone());
+
+        let handler = self.limited("HandleResponse", self.next().handle_response(response));
+        match timeout::within(timeout, handler).await {
+            Ok(result) => result,
+            Err(_) => match original {
+                Some(resp) => {
+                    let fallback = HttpResponse {
+                        r#continue: true,
+                        status_code: resp.status_code,
+                        headers: resp.headers,
+                        body: resp.body,
+                        ..Default::default()
+                    };
+                    Ok(self.fail_open("HandleResponse", timeout, fallback))
+                }
+                None => Err(self.fail_closed("HandleResponse", timeout)),
+            },
+        }
+    }
+
+    /// Records a timed-out RPC that is answered with a pass-through response.
+    fn fail_open<T>(&self, rpc: &str, timeout: Option<Duration>, fallback: T) -> Response<T> {
+        self.stats.record_timeout_fail_open();
+        warn!(
+            "{} exceeded deadline of {:?}, continuing unchanged ({})",
+            rpc,
+            timeout.unwrap_or_default(),
+            TimeoutPolicy::FailOpen.as_str()
+        );
+        Response::new(fallback)
+    }
+
+    /// Records a timed-out RPC that is rejected with `DeadlineExceeded`.
+    fn fail_closed(&self, rpc: &str, timeout: Option<Duration>) -> Status {
+        let timeout = timeout.unwrap_or_default();
+        self.stats.record_timeout_fail_closed();
+        warn!(
+            "{} exceeded deadline of {:?}, rejecting ({})",
+            rpc,
+            timeout,
+            TimeoutPolicy::FailClosed.as_str()
+        );
+        Status::deadline_exceeded(format!("{} exceeded deadline of {:?}", rpc, timeout))
     }
 }
 
 #[tonic::async_trait]
 impl<P: Plugin> PluginService for PluginAdapter<P> {
     async fn get_metadata(&self, request: Request<()>) -> Result<Response<Metadata>, Status> {
-        self.plugin.get_metadata(request).await
+        let timeout = timeout::effective_timeout(self.rpc_timeout, request.metadata());
+        let offered = version::offered(request.metadata());
+        let mut response = match timeout::within(timeout, self.plugin.get_metadata(request)).await {
+            Ok(result) => result?,
+            Err(_) => return Err(self.fail_closed("GetMetadata", timeout)),
+        };
+
+        // Negotiate the protocol version alongside the metadata.
+        version::report(
+            response.metadata_mut(),
+            &self.protocol_versions,
+            offered.as_deref(),
+        );
+        Ok(response)
     }
 
     async fn get_capabilities(
         &self,
         request: Request<()>,
     ) -> Result<Response<Capabilities>, Status> {
-        self.plugin.get_capabilities(request).await
+        let timeout = timeout::effective_timeout(self.rpc_timeout, request.metadata());
+        let mut response =
+            match timeout::within(timeout, self.plugin.get_capabilities(request)).await {
+                Ok(result) => result?,
+                Err(_) => return Err(self.fail_closed("GetCapabilities", timeout)),
+            };
+
+        // Tell the host which bodies it need not send alongside the flows.
+        body::report_bodyless(response.metadata_mut(), &self.plugin);
+        Ok(response)
     }
 
     async fn configure(&self, request: Request<PluginConfig>) -> Result<Response<()>, Status> {
-        self.plugin.configure(request).await
+        let timeout = timeout::effective_timeout(self.rpc_timeout, request.metadata());
+        match timeout::within(timeout, self.plugin.configure(request)).await {
+            Ok(result) => result,
+            Err(_) => Err(self.fail_closed("Configure", timeout)),
+        }
     }
 
     async fn stop(&self, request: Request<()>) -> Result<Response<()>, Status> {
-        self.plugin.stop(request).await
+        let timeout = timeout::effective_timeout(self.rpc_timeout, request.metadata());
+        match timeout::within(timeout, self.plugin.stop(request)).await {
+            Ok(result) => result,
+            Err(_) => Err(self.fail_closed("Stop", timeout)),
+        }
     }
 
     async fn check_health(&self, request: Request<()>) -> Result<Response<()>, Status> {
-        self.plugin.check_health(request).await
+        let timeout = timeout::effective_timeout(self.rpc_timeout, request.metadata());
+        match timeout::within(timeout, self.plugin.check_health(request)).await {
+            Ok(result) => result,
+            Err(_) => Err(self.fail_closed("CheckHealth", timeout)),
+        }
     }
 
     async fn check_ready(&self, request: Request<()>) -> Result<Response<()>, Status> {
-        self.plugin.check_ready(request).await
+        if let Some(limit) = &self.concurrency_limit {
+            if limit.is_saturated() {
+                return Err(Status::unavailable(format!(
+                    "plugin is saturated with {} calls in flight",
+                    limit.max_in_flight()
+                )));
+            }
+        }
+
+        let timeout = timeout::effective_timeout(self.rpc_timeout, request.metadata());
+        match timeout::within(timeout, self.plugin.check_ready(request)).await {
+            Ok(result) => result,
+            Err(_) => Err(self.fail_closed("CheckReady", timeout)),
+        }
     }
 
     async fn handle_request(
         &self,
         request: Request<HttpRequest>,
     ) -> Result<Response<HttpResponse>, Status> {
-        self.plugin.handle_request(request).await
+        #[cfg(feature = "record")]
+        let pending = self.recorder.as_ref().map(|recorder| {
+            recorder.begin(RecordedInput::HandleRequest {
+                request: request.get_ref().clone(),
+            })
+        });
+
+        let original =
+            body::omission_requested(request.metadata()).then(|| request.get_ref().body.clone());
+
+        let mut result = self.process_request(request).await;
+
+        // Record what the plugin returned, before any body is left out of it.
+        #[cfg(feature = "record")]
+        if let Some(pending) = pending {
+            pending.finish(&result);
+        }
+
+        if let Ok(response) = &mut result {
+            if let Some(original) = &original {
+                body::omit_unchanged_request(response, original);
+            }
+            let limit = self.max_encoding_message_size;
+            if let Some(status) =
+                codec::encoding_limit_exceeded("HandleRequest", response.get_ref(), limit)
+            {
+                result = Err(status);
+            }
+        }
+        result
     }
 
     async fn handle_response(
         &self,
         response: Request<HttpResponse>,
     ) -> Result<Response<HttpResponse>, Status> {
-        self.plugin.handle_response(response).await
+        #[cfg(feature = "record")]
+        let pending = self.recorder.as_ref().map(|recorder| {
+            recorder.begin(RecordedInput::HandleResponse {
+                response: response.get_ref().clone(),
+            })
+        });
+
+        let original =
+            body::omission_requested(response.metadata()).then(|| response.get_ref().body.clone());
+
+        let mut result = self.process_response(response).await;
+
+        // Record what the plugin returned, before any body is left out of it.
+        #[cfg(feature = "record")]
+        if let Some(pending) = pending {
+            pending.finish(&result);
+        }
+
+        if let Ok(response) = &mut result {
+            if let Some(original) = &original {
+                body::omit_unchanged_response(response, original);
+            }
+            let limit = self.max_encoding_message_size;
+            if let Some(status) =
+                codec::encoding_limit_exceeded("HandleResponse", response.get_ref(), limit)
+            {
+                result = Err(status);
+            }
+        }
+        result
     }
 }
@@ -0,0 +1,623 @@
+use crate::plugin::{Plugin, PluginAdapter};
+use crate::proto::{plugin_server::Plugin as PluginService, HttpRequest, HttpResponse};
+use crate::{PluginError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tonic::{Request, Response, Status};
+use tracing::warn;
+
+/// Headers redacted by default, since they carry credentials.
+const DEFAULT_REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// Value written in place of a redacted header.
+const REDACTED: &str = "[REDACTED]";
+
+/// One recorded `HandleRequest` or `HandleResponse` call, a line of a recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedCall {
+    /// When the call started, in milliseconds since the Unix epoch.
+    pub started_at_ms: u64,
+
+    /// How long the call took, in microseconds.
+    pub duration_us: u64,
+
+    /// The RPC and its input.
+    #[serde(flatten)]
+    pub input: RecordedInput,
+
+    /// What the plugin answered.
+    pub output: RecordedOutput,
+}
+
+/// Input of a recorded call, tagged with the RPC name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rpc")]
+pub enum RecordedInput {
+    /// A `HandleRequest` call.
+    HandleRequest {
+        /// The request received from the host.
+        request: HttpRequest,
+    },
+
+    /// A `HandleResponse` call.
+    HandleResponse {
+        /// The response received from the host.
+        response: HttpResponse,
+    },
+}
+
+impl RecordedInput {
+    /// Returns the name of the recorded RPC.
+    pub fn rpc(&self) -> &'static str {
+        match self {
+            RecordedInput::HandleRequest { .. } => "HandleRequest",
+            RecordedInput::HandleResponse { .. } => "HandleResponse",
+        }
+    }
+}
+
+/// Output of a recorded call: a response or a gRPC status.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordedOutput {
+    /// The plugin returned a response.
+    Response(Box<HttpResponse>),
+
+    /// The plugin returned an error status.
+    Status {
+        /// Numeric gRPC status code.
+        code: i32,
+        /// Status message.
+        message: String,
+    },
+}
+
+impl RecordedOutput {
+    fn from_result(result: &std::result::Result<Response<HttpResponse>, Status>) -> Self {
+        match result {
+            Ok(response) => RecordedOutput::Response(Box::new(response.get_ref().clone())),
+            Err(status) => RecordedOutput::Status {
+                code: status.code() as i32,
+                message: status.message().to_string(),
+            },
+        }
+    }
+}
+
+/// Appends `HandleRequest`/`HandleResponse` calls to a JSONL file.
+///
+/// Each line is a [`RecordedCall`] with the input, output and timing of one call. Values of
+/// redacted headers (by default `authorization`, `proxy-authorization`, `cookie` and
+/// `set-cookie`) are replaced in inputs and outputs before writing, unless
+/// [`unredacted_inputs`](Self::unredacted_inputs) is set. Lines are written by a dedicated
+/// thread, so handlers never wait on the file; calls are dropped with a warning while
+/// [`QUEUE_CAPACITY`](Self::QUEUE_CAPACITY) lines are waiting. Failing to write is logged
+/// and never fails the call. Dropping the recorder writes the queued lines first.
+pub struct Recorder {
+    sender: Option<SyncSender<String>>,
+    writer: Option<JoinHandle<()>>,
+    redaction: Redaction,
+    redact_inputs: bool,
+}
+
+impl Recorder {
+    /// Number of recorded lines that may wait for the writer thread.
+    pub const QUEUE_CAPACITY: usize = 1024;
+
+    /// Opens `path` for appending, creating it if needed, and starts the writer thread.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path.as_ref())?;
+        let (sender, receiver) = mpsc::sync_channel::<String>(Self::QUEUE_CAPACITY);
+        let writer = thread::Builder::new()
+            .name("mcpd-plugin-recorder".to_string())
+            .spawn(move || {
+                // One write per line, so lines never interleave with other writers.
+                for line in receiver {
+                    if let Err(err) = file.write_all(line.as_bytes()) {
+                        warn!("Failed to write recorded call: {}", err);
+                    }
+                }
+            })?;
+        Ok(Self {
+            sender: Some(sender),
+            writer: Some(writer),
+            redaction: Redaction::default(),
+            redact_inputs: true,
+        })
+    }
+
+    /// Also redacts the header `name` (case-insensitive).
+    pub fn redact_header(mut self, name: impl Into<String>) -> Self {
+        self.redaction.add(name.into());
+        self
+    }
+
+    /// Records inputs as received, credentials included (`--record-unredacted-inputs`).
+    ///
+    /// A plugin that checks redacted headers, e.g. an auth plugin reading `authorization`,
+    /// sees `[REDACTED]` when a redacted recording is replayed and answers differently.
+    /// Recording inputs unredacted makes such recordings replayable, at the cost of writing
+    /// credentials to the file. Outputs are still redacted.
+    pub fn unredacted_inputs(mut self) -> Self {
+        self.redact_inputs = false;
+        self
+    }
+
+    /// Starts recording a call with `input`.
+    pub(crate) fn begin(&self, input: RecordedInput) -> PendingCall<'_> {
+        let started_at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        PendingCall {
+            recorder: self,
+            input,
+            started_at_ms,
+            started: Instant::now(),
+        }
+    }
+
+    fn write(&self, mut call: RecordedCall) {
+        if self.redact_inputs {
+            self.redaction.apply_input(&mut call.input);
+        }
+        self.redaction.apply_output(&mut call.output);
+        let line = match serde_json::to_string(&call) {
+            Ok(line) => line + "\n",
+            Err(err) => {
+                warn!(
+                    "Failed to encode recorded {} call: {}",
+                    call.input.rpc(),
+                    err
+                );
+                return;
+            }
+        };
+
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        match sender.try_send(line) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => warn!(
+                "Recording queue is full, dropping {} call",
+                call.input.rpc()
+            ),
+            Err(TrySendError::Disconnected(_)) => warn!(
+                "Recording writer has stopped, dropping {} call",
+                call.input.rpc()
+            ),
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        // Closing the queue lets the writer finish the queued lines and exit.
+        drop(self.sender.take());
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// A call being recorded, written once its result is known.
+pub(crate) struct PendingCall<'a> {
+    recorder: &'a Recorder,
+    input: RecordedInput,
+    started_at_ms: u64,
+    started: Instant,
+}
+
+impl PendingCall<'_> {
+    /// Writes the call with its `result`.
+    pub(crate) fn finish(self, result: &std::result::Result<Response<HttpResponse>, Status>) {
+        self.recorder.write(RecordedCall {
+            started_at_ms: self.started_at_ms,
+            duration_us: self.started.elapsed().as_micros() as u64,
+            input: self.input,
+            output: RecordedOutput::from_result(result),
+        });
+    }
+}
+
+/// Replays a recording against a plugin and compares the outputs.
+///
+/// Calls go through a [`PluginAdapter`], so configure it as the recorded plugin was served
+/// (middleware, timeouts, in-flight limit) to replay the same behaviour. Redacted headers
+/// are redacted in the replayed outputs too, so they compare equal; configure the same
+/// extra headers as the [`Recorder`] did. Redacted inputs cannot be replayed faithfully,
+/// since the plugin receives `[REDACTED]` in their place: record with
+/// [`Recorder::unredacted_inputs`] to replay plugins that read them.
+pub struct Replay {
+    calls: Vec<RecordedCall>,
+    redaction: Redaction,
+}
+
+impl Replay {
+    /// Reads the recording at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut calls = Vec::new();
+        for (index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let call = serde_json::from_str(&line).map_err(|err| {
+                PluginError::InvalidInput(format!("{} line {}: {}", path.display(), index + 1, err))
+            })?;
+            calls.push(call);
+        }
+        Ok(Self {
+            calls,
+            redaction: Redaction::default(),
+        })
+    }
+
+    /// Also redacts the header `name` (case-insensitive) in replayed outputs.
+    pub fn redact_header(mut self, name: impl Into<String>) -> Self {
+        self.redaction.add(name.into());
+        self
+    }
+
+    /// Returns the recorded calls.
+    pub fn calls(&self) -> &[RecordedCall] {
+        &self.calls
+    }
+
+    /// Feeds every recorded input through `adapter`, in order, and reports the calls whose
+    /// output differs from the recording.
+    pub async fn run<P: Plugin>(&self, adapter: &PluginAdapter<P>) -> ReplayReport {
+        let mut mismatches = Vec::new();
+        for (index, call) in self.calls.iter().enumerate() {
+            let result = match &call.input {
+                RecordedInput::HandleRequest { request } => {
+                    PluginService::handle_request(adapter, Request::new(request.clone())).await
+                }
+                RecordedInput::HandleResponse { response } => {
+                    PluginService::handle_response(adapter, Request::new(response.clone())).await
+                }
+            };
+
+            let mut actual = RecordedOutput::from_result(&result);
+            self.redaction.apply_output(&mut actual);
+            if actual != call.output {
+                mismatches.push(Mismatch {
+                    index,
+                    rpc: call.input.rpc(),
+                    expected: call.output.clone(),
+                    actual,
+                });
+            }
+        }
+
+        ReplayReport {
+            replayed: self.calls.len(),
+            mismatches,
+        }
+    }
+}
+
+/// Outcome of a [`Replay`].
+#[derive(Debug)]
+pub struct ReplayReport {
+    /// Number of calls replayed.
+    pub replayed: usize,
+
+    /// Calls whose output differs from the recording.
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl ReplayReport {
+    /// Returns whether every replayed output matched the recording.
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+impl std::fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} of {} replayed calls differ from the recording",
+            self.mismatches.len(),
+            self.replayed
+        )?;
+        for mismatch in &self.mismatches {
+            write!(f, "{}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+/// A replayed call whose output differs from the recording.
+#[derive(Debug)]
+pub struct Mismatch {
+    /// Position of the call in the recording, starting at 0.
+    pub index: usize,
+
+    /// Name of the RPC.
+    pub rpc: &'static str,
+
+    /// Output in the recording.
+    pub expected: RecordedOutput,
+
+    /// Output of the replay.
+    pub actual: RecordedOutput,
+}
+
+impl std::fmt::Display for Mismatch {
+    /// Lists each differing field with its recorded and replayed value.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "call {} ({}):", self.index, self.rpc)?;
+        let expected = serde_json::to_value(&self.expected).unwrap_or_default();
+        let actual = serde_json::to_value(&self.actual).unwrap_or_default();
+        let mut differences = Vec::new();
+        diff("", &expected, &actual, &mut differences);
+        for (path, expected, actual) in differences {
+            writeln!(f, "  {}: recorded {}, replayed {}", path, expected, actual)?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects the paths at which `expected` and `actual` differ.
+fn diff(
+    path: &str,
+    expected: &serde_json::Value,
+    actual: &serde_json::Value,
+    differences: &mut Vec<(String, String, String)>,
+) {
+    use serde_json::Value;
+
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            let mut keys: Vec<&String> = expected.keys().chain(actual.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                diff(
+                    &child,
+                    expected.get(key).unwrap_or(&Value::Null),
+                    actual.get(key).unwrap_or(&Value::Null),
+                    differences,
+                );
+            }
+        }
+        _ if expected != actual => {
+            let path = if path.is_empty() { "output" } else { path };
+            differences.push((path.to_string(), expected.to_string(), actual.to_string()));
+        }
+        _ => {}
+    }
+}
+
+/// Header names whose values are redacted, lowercased.
+struct Redaction {
+    headers: Vec<String>,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Self {
+            headers: DEFAULT_REDACTED_HEADERS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl Redaction {
+    fn add(&mut self, name: String) {
+        self.headers.push(name.to_ascii_lowercase());
+    }
+
+    fn apply_input(&self, input: &mut RecordedInput) {
+        match input {
+            RecordedInput::HandleRequest { request } => self.apply_request(request),
+            RecordedInput::HandleResponse { response } => self.apply_response(response),
+        }
+    }
+
+    fn apply_output(&self, output: &mut RecordedOutput) {
+        if let RecordedOutput::Response(response) = output {
+            self.apply_response(response);
+        }
+    }
+
+    fn apply_request(&self, request: &mut HttpRequest) {
+        self.apply_headers(&mut request.headers);
+    }
+
+    fn apply_response(&self, response: &mut HttpResponse) {
+        self.apply_headers(&mut response.headers);
+        if let Some(request) = &mut response.modified_request {
+            self.apply_request(request);
+        }
+    }
+
+    fn apply_headers(&self, headers: &mut std::collections::HashMap<String, String>) {
+        for (name, value) in headers.iter_mut() {
+            if self.headers.contains(&name.to_ascii_lowercase()) {
+                *value = REDACTED.to_string();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::{Middleware, Next};
+    use std::path::PathBuf;
+
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "mcpd-record-{}-{}.jsonl",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_file(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// Continues requests carrying the right bearer token and rejects the rest.
+    struct Auth;
+
+    #[tonic::async_trait]
+    impl Plugin for Auth {
+        async fn handle_request(
+            &self,
+            request: Request<HttpRequest>,
+        ) -> std::result::Result<Response<HttpResponse>, Status> {
+            let authorized = request.get_ref().headers.get("Authorization")
+                == Some(&"Bearer secret".to_string());
+            Ok(Response::new(HttpResponse {
+                r#continue: authorized,
+                status_code: if authorized { 0 } else { 401 },
+                ..Default::default()
+            }))
+        }
+    }
+
+    /// Marks every request result, standing in for middleware configured on the server.
+    struct Stamp;
+
+    #[tonic::async_trait]
+    impl Middleware for Stamp {
+        async fn handle_request(
+            &self,
+            request: Request<HttpRequest>,
+            next: Next<'_>,
+        ) -> std::result::Result<Response<HttpResponse>, Status> {
+            let mut response = next.handle_request(request).await?;
+            response
+                .get_mut()
+                .headers
+                .insert("x-stamped".to_string(), "yes".to_string());
+            Ok(response)
+        }
+    }
+
+    fn request(token: &str) -> HttpRequest {
+        HttpRequest {
+            method: "GET".to_string(),
+            path: "/tools".to_string(),
+            headers: [("Authorization".to_string(), format!("Bearer {}", token))].into(),
+            ..Default::default()
+        }
+    }
+
+    /// Sends `requests` through `adapter`, then drops it so every line is written.
+    async fn record<P: Plugin>(adapter: PluginAdapter<P>, requests: &[HttpRequest]) {
+        for request in requests {
+            let _ = PluginService::handle_request(&adapter, Request::new(request.clone())).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn writes_a_redacted_line_per_call() {
+        let file = TempFile::new("lines");
+        let recorder = Recorder::create(&file.0).unwrap();
+        record(
+            PluginAdapter::new(Auth).record(recorder),
+            &[request("secret"), request("wrong")],
+        )
+        .await;
+
+        let replay = Replay::open(&file.0).unwrap();
+        assert_eq!(replay.calls().len(), 2);
+        for call in replay.calls() {
+            let RecordedInput::HandleRequest { request } = &call.input else {
+                panic!("unexpected {}", call.input.rpc());
+            };
+            assert_eq!(request.headers["Authorization"], REDACTED);
+        }
+    }
+
+    #[tokio::test]
+    async fn redacted_credentials_cannot_be_replayed() {
+        let file = TempFile::new("redacted");
+        let recorder = Recorder::create(&file.0).unwrap();
+        record(
+            PluginAdapter::new(Auth).record(recorder),
+            &[request("secret")],
+        )
+        .await;
+
+        let report = Replay::open(&file.0)
+            .unwrap()
+            .run(&PluginAdapter::new(Auth))
+            .await;
+        assert_eq!(report.mismatches.len(), 1, "{}", report);
+    }
+
+    #[tokio::test]
+    async fn unredacted_inputs_replay() {
+        let file = TempFile::new("unredacted");
+        let recorder = Recorder::create(&file.0).unwrap().unredacted_inputs();
+        record(
+            PluginAdapter::new(Auth).record(recorder),
+            &[request("secret"), request("wrong")],
+        )
+        .await;
+
+        let report = Replay::open(&file.0)
+            .unwrap()
+            .run(&PluginAdapter::new(Auth))
+            .await;
+        assert!(report.is_ok(), "{}", report);
+        assert_eq!(report.replayed, 2);
+    }
+
+    #[tokio::test]
+    async fn replays_through_the_adapter_configuration() {
+        let file = TempFile::new("middleware");
+        let recorder = Recorder::create(&file.0).unwrap().unredacted_inputs();
+        record(
+            PluginAdapter::new(Auth).middleware(Stamp).record(recorder),
+            &[request("secret")],
+        )
+        .await;
+        let replay = Replay::open(&file.0).unwrap();
+
+        let report = replay
+            .run(&PluginAdapter::new(Auth).middleware(Stamp))
+            .await;
+        assert!(report.is_ok(), "{}", report);
+
+        let report = replay.run(&PluginAdapter::new(Auth)).await;
+        assert_eq!(report.mismatches.len(), 1);
+        assert!(report.to_string().contains("x-stamped"), "{}", report);
+    }
+}
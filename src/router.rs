@@ -0,0 +1,529 @@
+use crate::proto::{HttpRequest, HttpResponse};
+use crate::{PluginError, Result};
+use std::collections::HashMap;
+use std::future::Future;
+use tonic::{Request, Response, Status};
+
+/// Dispatches [`HttpRequest`]s to handlers by method and path.
+///
+/// Routes are tried in the order they were added and the first match wins. Requests that
+/// match no route go to the fallback handler, which by default continues unchanged.
+///
+/// Path patterns are matched against the percent-decoded request path, segment by segment
+/// (empty segments are ignored, so trailing slashes don't matter). Paths with `.` or `..`
+/// segments (including `..;` and `\`-separated forms) or malformed escapes match no route
+/// and go to the fallback, so e.g. a bypass for `/public/**` doesn't apply to
+/// `/public/../admin`, `/public/%2e%2e/admin` or `/public/..;/admin`.
+///
+///
+/// | Segment    | Matches                                            |
+/// |------------|----------------------------------------------------|
+/// | `tools`    | exactly `tools`                                    |
+/// | `{name}`   | any single segment, captured as `name`             |
+/// | `*`        | any single segment                                 |
+/// | `{*rest}`  | all remaining segments (last only), captured       |
+/// | `**`       | all remaining segments (last only)                 |
+///
+/// A method of `*` matches any method; other methods are compared case-insensitively.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use mcpd_plugins_sdk::{HttpRequest, HttpResponse, PathParams, Plugin, RequestRouter};
+/// use tonic::{Request, Response, Status};
+///
+/// struct MyPlugin {
+///     router: RequestRouter,
+/// }
+///
+/// impl MyPlugin {
+///     fn new() -> Self {
+///         let router = RequestRouter::new()
+///             .bypass("GET", "/health")
+///             .route("POST", "/tools/{name}", |req: HttpRequest, params: PathParams| async move {
+///                 tracing::info!("Calling tool {}", &params["name"]);
+///                 Ok(HttpResponse {
+///                     r#continue: true,
+///                     modified_request: Some(req),
+///                     ..Default::default()
+///                 })
+///             });
+///         Self { router }
+///     }
+/// }
+///
+/// #[tonic::async_trait]
+/// impl Plugin for MyPlugin {
+///     async fn handle_request(
+///         &self,
+///         request: Request<HttpRequest>,
+///     ) -> Result<Response<HttpResponse>, Status> {
+///         self.router.handle(request).await
+///     }
+/// }
+/// ```
+#[derive(Default)]
+pub struct RequestRouter {
+    routes: Vec<Route>,
+    fallback: Option<Box<dyn RouteHandler>>,
+}
+
+/// Handles requests matched by a [`RequestRouter`] route.
+///
+/// Implemented for async functions and closures taking the request and its [`PathParams`];
+/// closures need their argument types annotated.
+#[tonic::async_trait]
+pub trait RouteHandler: Send + Sync + 'static {
+    /// Handles a matched request.
+    async fn handle(
+        &self,
+        request: HttpRequest,
+        params: PathParams,
+    ) -> std::result::Result<HttpResponse, Status>;
+}
+
+#[tonic::async_trait]
+impl<F, Fut> RouteHandler for F
+where
+    F: Fn(HttpRequest, PathParams) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = std::result::Result<HttpResponse, Status>> + Send,
+{
+    async fn handle(
+        &self,
+        request: HttpRequest,
+        params: PathParams,
+    ) -> std::result::Result<HttpResponse, Status> {
+        self(request, params).await
+    }
+}
+
+/// Parameters captured from the request path by `{name}` and `{*name}` segments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathParams(HashMap<String, String>);
+
+impl PathParams {
+    /// Returns the value captured for `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+}
+
+impl std::ops::Index<&str> for PathParams {
+    type Output = str;
+
+    fn index(&self, name: &str) -> &str {
+        self.get(name)
+            .unwrap_or_else(|| panic!("no path parameter named {:?}", name))
+    }
+}
+
+struct Route {
+    method: String,
+    pattern: Vec<Segment>,
+    handler: Box<dyn RouteHandler>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Any,
+    Rest(Option<String>),
+}
+
+/// Handler that continues with the request unchanged.
+struct Continue;
+
+#[tonic::async_trait]
+impl RouteHandler for Continue {
+    async fn handle(
+        &self,
+        _request: HttpRequest,
+        _params: PathParams,
+    ) -> std::result::Result<HttpResponse, Status> {
+        Ok(HttpResponse {
+            r#continue: true,
+            ..Default::default()
+        })
+    }
+}
+
+impl RequestRouter {
+    /// Creates a router without routes, whose fallback continues unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Routes requests matching `method` and `pattern` to `handler`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` is invalid, see [`try_route`](Self::try_route).
+    pub fn route<H: RouteHandler>(self, method: &str, pattern: &str, handler: H) -> Self {
+        self.try_route(method, pattern, handler)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like [`route`](Self::route), but returns an error for an invalid pattern: one not
+    /// starting with `/`, with an unterminated or empty `{}` capture, or with a rest segment
+    /// (`**` or `{*name}`) that is not last.
+    pub fn try_route<H: RouteHandler>(
+        mut self,
+        method: &str,
+        pattern: &str,
+        handler: H,
+    ) -> Result<Self> {
+        self.routes.push(Route {
+            method: method.to_string(),
+            pattern: parse_pattern(pattern)?,
+            handler: Box::new(handler),
+        });
+        Ok(self)
+    }
+
+    /// Lets requests matching `method` and `pattern` continue unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` is invalid, see [`try_route`](Self::try_route).
+    pub fn bypass(self, method: &str, pattern: &str) -> Self {
+        self.route(method, pattern, Continue)
+    }
+
+    /// Adds a bypass route for every entry of a comma-separated list, e.g. from
+    /// `custom_config`.
+    ///
+    /// Each entry is a path pattern, optionally preceded by a method:
+    /// `"/health, GET /ready, POST /public/**"`. Entries without a method match any method.
+    pub fn bypass_list(mut self, list: &str) -> Result<Self> {
+        for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (method, pattern) = match entry.split_once(char::is_whitespace) {
+                Some((method, pattern)) => (method, pattern.trim()),
+                None => ("*", entry),
+            };
+            self = self.try_route(method, pattern, Continue)?;
+        }
+        Ok(self)
+    }
+
+    /// Handles requests that match no route with `handler` instead of continuing unchanged.
+    pub fn fallback<H: RouteHandler>(mut self, handler: H) -> Self {
+        self.fallback = Some(Box::new(handler));
+        self
+    }
+
+    /// Dispatches a request to the first matching route, or to the fallback.
+    ///
+    /// The signature matches [`Plugin::handle_request()`](crate::Plugin::handle_request), so
+    /// a plugin can delegate to the router directly.
+    pub async fn handle(
+        &self,
+        request: Request<HttpRequest>,
+    ) -> std::result::Result<Response<HttpResponse>, Status> {
+        let request = request.into_inner();
+
+        let matched = path_segments(&request.path).and_then(|path| {
+            self.routes.iter().find_map(|route| {
+                route
+                    .matches(&request.method, &path)
+                    .map(|params| (route.handler.as_ref(), params))
+            })
+        });
+
+        let response = match (matched, &self.fallback) {
+            (Some((handler, params)), _) => handler.handle(request, params).await?,
+            (None, Some(fallback)) => fallback.handle(request, PathParams::default()).await?,
+            (None, None) => Continue.handle(request, PathParams::default()).await?,
+        };
+
+        Ok(Response::new(response))
+    }
+}
+
+impl Route {
+    /// Returns the captured parameters if a request with `method` and the decoded `path`
+    /// segments matches this route.
+    fn matches(&self, method: &str, path: &[String]) -> Option<PathParams> {
+        if self.method != "*" && !self.method.eq_ignore_ascii_case(method) {
+            return None;
+        }
+
+        let mut params = HashMap::new();
+        let mut path = path.iter().map(String::as_str);
+
+        for segment in &self.pattern {
+            match segment {
+                Segment::Rest(name) => {
+                    if let Some(name) = name {
+                        params.insert(name.clone(), path.collect::<Vec<_>>().join("/"));
+                    }
+                    return Some(PathParams(params));
+                }
+                Segment::Literal(literal) => {
+                    if path.next()? != literal {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    params.insert(name.clone(), path.next()?.to_string());
+                }
+                Segment::Any => {
+                    path.next()?;
+                }
+            }
+        }
+
+        path.next().is_none().then_some(PathParams(params))
+    }
+}
+
+/// Splits a request path into its non-empty, percent-decoded segments.
+///
+/// Returns `None` for paths that can't be matched safely: with malformed escapes, escapes
+/// that don't decode to UTF-8, or `.` and `..` segments (also when encoded, e.g. `%2e%2e`,
+/// followed by matrix parameters, e.g. `..;`, or separated by `\`), which the upstream
+/// server may resolve to a path outside the matched route.
+fn path_segments(path: &str) -> Option<Vec<String>> {
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut bytes = path.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let high = char::from(bytes.next()?).to_digit(16)?;
+            let low = char::from(bytes.next()?).to_digit(16)?;
+            decoded.push((high * 16 + low) as u8);
+        } else {
+            decoded.push(byte);
+        }
+    }
+
+    let decoded = String::from_utf8(decoded).ok()?;
+    let segments: Vec<String> = decoded
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+    if segments.iter().any(|s| is_dot_segment(s)) {
+        return None;
+    }
+    Some(segments)
+}
+
+/// Returns whether `segment` is, or contains, a `.` or `..` segment as some servers read it:
+/// with matrix parameters after `;` (`..;x=y`, as Tomcat does) or with `\` as a separator
+/// (as IIS does).
+fn is_dot_segment(segment: &str) -> bool {
+    segment.split('\\').any(|part| {
+        let name = part.split(';').next().unwrap_or_default();
+        name == "." || name == ".."
+    })
+}
+
+/// Parses a path pattern into its segments.
+fn parse_pattern(pattern: &str) -> Result<Vec<Segment>> {
+    let invalid = |reason: &str| {
+        PluginError::Configuration(format!("Invalid route pattern {:?}: {}", pattern, reason))
+    };
+
+    if !pattern.starts_with('/') {
+        return Err(invalid("must start with '/'"));
+    }
+
+    let segments: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let mut parsed = Vec::with_capacity(segments.len());
+
+    for (i, segment) in segments.iter().enumerate() {
+        let is_last = i + 1 == segments.len();
+
+        let parsed_segment = if *segment == "*" {
+            Segment::Any
+        } else if *segment == "**" {
+            Segment::Rest(None)
+        } else if let Some(inner) = segment.strip_prefix('{') {
+            let name = inner
+                .strip_suffix('}')
+                .ok_or_else(|| invalid("unterminated '{'"))?;
+            match name.strip_prefix('*') {
+                Some(rest) if !rest.is_empty() => Segment::Rest(Some(rest.to_string())),
+                None if !name.is_empty() => Segment::Param(name.to_string()),
+                _ => return Err(invalid("empty capture name")),
+            }
+        } else {
+            Segment::Literal(segment.to_string())
+        };
+
+        if matches!(parsed_segment, Segment::Rest(_)) && !is_last {
+            return Err(invalid("'**' and '{*name}' must be the last segment"));
+        }
+        parsed.push(parsed_segment);
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the parameters captured when `path` matches `pattern`.
+    fn matches(method: &str, pattern: &str, path: &str) -> Option<PathParams> {
+        let route = Route {
+            method: method.to_string(),
+            pattern: parse_pattern(pattern).unwrap(),
+            handler: Box::new(Continue),
+        };
+        route.matches("GET", &path_segments(path)?)
+    }
+
+    fn params(pairs: &[(&str, &str)]) -> PathParams {
+        PathParams(
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn matches_literals_ignoring_empty_segments() {
+        assert_eq!(matches("GET", "/health", "/health"), Some(params(&[])));
+        assert_eq!(matches("GET", "/health", "/health/"), Some(params(&[])));
+        assert_eq!(matches("GET", "/a/b", "//a//b"), Some(params(&[])));
+        assert_eq!(matches("GET", "/", "/"), Some(params(&[])));
+        assert_eq!(matches("GET", "/health", "/healthz"), None);
+        assert_eq!(matches("GET", "/health", "/health/live"), None);
+        assert_eq!(matches("GET", "/health/live", "/health"), None);
+    }
+
+    #[test]
+    fn matches_methods() {
+        assert!(matches("get", "/health", "/health").is_some());
+        assert!(matches("*", "/health", "/health").is_some());
+        assert!(matches("POST", "/health", "/health").is_none());
+    }
+
+    #[test]
+    fn captures_params() {
+        assert_eq!(
+            matches(
+                "GET",
+                "/servers/{server}/tools/{name}",
+                "/servers/time/tools/now"
+            ),
+            Some(params(&[("server", "time"), ("name", "now")]))
+        );
+        assert_eq!(matches("GET", "/tools/{name}", "/tools"), None);
+        assert_eq!(matches("GET", "/tools/{name}", "/tools/a/b"), None);
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(matches("GET", "/tools/*/call", "/tools/now/call").is_some());
+        assert!(matches("GET", "/tools/*", "/tools").is_none());
+        assert!(matches("GET", "/public/**", "/public").is_some());
+        assert!(matches("GET", "/public/**", "/public/a/b/c").is_some());
+        assert!(matches("GET", "/public/**", "/private/a").is_none());
+        assert_eq!(
+            matches("GET", "/files/{*path}", "/files/a/b.txt"),
+            Some(params(&[("path", "a/b.txt")]))
+        );
+        assert_eq!(
+            matches("GET", "/files/{*path}", "/files"),
+            Some(params(&[("path", "")]))
+        );
+    }
+
+    #[test]
+    fn matches_decoded_paths() {
+        assert_eq!(
+            matches("GET", "/tools/{name}", "/tools/get%20time"),
+            Some(params(&[("name", "get time")]))
+        );
+        assert!(matches("GET", "/public/**", "/%70ublic/a").is_some());
+        // An encoded slash separates segments like the upstream server may decode it.
+        assert!(matches("GET", "/tools/{name}", "/tools/a%2Fb").is_none());
+    }
+
+    #[test]
+    fn rejects_traversal() {
+        for path in [
+            "/public/../admin",
+            "/public/./admin",
+            "/public/..",
+            "/public/%2e%2e/admin",
+            "/public/%2E%2E/admin",
+            "/public/.%2e/admin",
+            "/public%2F..%2Fadmin",
+            "/public/%2e/admin",
+            "/public/..;/admin",
+            "/public/..;jsessionid=1/admin",
+            "/public/.;/admin",
+            "/public/%2e%2e;/admin",
+            "/public\\..\\admin",
+            "/public/..\\admin",
+            "/public/%5c..%5cadmin",
+        ] {
+            assert_eq!(matches("GET", "/public/**", path), None, "{}", path);
+        }
+
+        // Dots, matrix parameters and backslashes within a name are not traversal.
+        for path in [
+            "/public/a;v=1",
+            "/public/...",
+            "/public/a\\b",
+            "/public/.well-known",
+        ] {
+            assert!(matches("GET", "/public/**", path).is_some(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_escapes() {
+        for path in ["/public/%", "/public/%2", "/public/%zz", "/public/%ff"] {
+            assert_eq!(matches("GET", "/public/**", path), None, "{}", path);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        for pattern in [
+            "health",
+            "/{name",
+            "/{}",
+            "/{*}",
+            "/**/tail",
+            "/{*rest}/tail",
+        ] {
+            assert!(parse_pattern(pattern).is_err(), "{}", pattern);
+        }
+    }
+
+    #[tokio::test]
+    async fn traversal_goes_to_the_fallback() {
+        let router = RequestRouter::new().bypass("*", "/public/**").fallback(
+            |_req: HttpRequest, _params: PathParams| async move {
+                Ok(HttpResponse {
+                    r#continue: false,
+                    status_code: 401,
+                    ..Default::default()
+                })
+            },
+        );
+
+        for (path, allowed) in [
+            ("/public/docs", true),
+            ("/admin", false),
+            ("/public/../admin", false),
+            ("/public/%2e%2e/admin", false),
+            ("/public/..;/admin", false),
+            ("/public\\..\\admin", false),
+        ] {
+            let request = HttpRequest {
+                method: "POST".to_string(),
+                path: path.to_string(),
+                ..Default::default()
+            };
+            let response = router.handle(Request::new(request)).await.unwrap();
+            assert_eq!(response.get_ref().r#continue, allowed, "{}", path);
+        }
+    }
+}
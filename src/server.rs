@@ -1,12 +1,37 @@
+#[cfg(unix)]
+use crate::activation::{self, InheritedListener};
+use crate::codec::{CodecOptions, Compression, LogOversized};
+use crate::concurrency::OverloadPolicy;
+use crate::handshake::Handshake;
+use crate::health;
+use crate::layer::{self, BoxPluginLayer, BoxPluginService};
+use crate::middleware::Middleware;
+use crate::peer::PeerCredentials;
 use crate::plugin::{Plugin, PluginAdapter};
 use crate::proto::plugin_server::PluginServer;
+#[cfg(feature = "record")]
+use crate::record::Recorder;
+use crate::socket::{self, UnixSocketOptions};
+use crate::stats::AdapterStats;
+use crate::timeout::TimeoutPolicy;
+use crate::version::ProtocolVersion;
+use crate::watchdog::{self, HostWatch};
 use crate::{PluginError, Result};
 use clap::Parser;
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::signal;
+use tonic::body::BoxBody;
+use tonic::transport::server::{Router, TcpIncoming};
 use tonic::transport::Server;
+use tower::{BoxError, Layer, Service};
 use tracing::{info, warn};
 
+#[cfg(unix)]
+use std::os::unix::io::RawFd;
+use tokio::net::TcpListener;
 #[cfg(unix)]
 use tokio::net::UnixListener;
 
@@ -14,23 +39,544 @@ use tokio::net::UnixListener;
 #[derive(Parser, Debug)]
 #[command(author, version, about = "mcpd plugin server", long_about = None)]
 struct Args {
-    /// Address to bind to (socket path for unix, host:port for tcp).
+    /// Address to bind to (socket path or @name for a Linux abstract socket, host:port for
+    /// tcp, descriptor number or LISTEN_FDNAMES entry for fd).
     #[arg(long)]
     address: String,
 
-    /// Network type (unix or tcp).
+    /// Network type (unix, tcp, or fd for an inherited listening socket).
     #[arg(long, default_value = "unix")]
     network: String,
+
+    /// Maximum time in milliseconds any single RPC may take.
+    #[arg(long, value_name = "MILLISECONDS")]
+    rpc_timeout_ms: Option<u64>,
+
+    /// What request/response handling returns when it exceeds its deadline.
+    #[arg(long, value_enum)]
+    timeout_policy: Option<TimeoutPolicy>,
+
+    /// Maximum number of HandleRequest/HandleResponse calls running at once.
+    #[arg(long)]
+    max_in_flight: Option<usize>,
+
+    /// What happens to calls beyond --max-in-flight (queue or shed).
+    #[arg(long, value_enum)]
+    overload_policy: Option<OverloadPolicy>,
+
+    /// Permissions for the unix socket file, in octal (e.g. 660).
+    #[arg(long, value_name = "MODE", value_parser = socket::parse_mode)]
+    socket_mode: Option<u32>,
+
+    /// Group (name or id) owning the unix socket file.
+    #[arg(long, value_name = "GROUP")]
+    socket_group: Option<String>,
+
+    /// Only admit unix socket connections from allowlisted peers (default: own user).
+    #[arg(long)]
+    verify_peer: bool,
+
+    /// Admit unix socket connections from this user id (implies --verify-peer).
+    #[arg(long = "peer-uid", value_name = "UID")]
+    peer_uids: Vec<u32>,
+
+    /// Admit unix socket connections from this group id (implies --verify-peer).
+    #[arg(long = "peer-gid", value_name = "GID")]
+    peer_gids: Vec<u32>,
+
+    /// Admit unix socket connections from this process id (implies --verify-peer).
+    #[arg(long = "peer-pid", value_name = "PID")]
+    peer_pids: Vec<i32>,
+
+    /// Admit unix socket connections from the parent process (implies --verify-peer).
+    #[arg(long)]
+    peer_parent: bool,
+
+    /// Interval in milliseconds between health polls for grpc.health.v1.
+    #[arg(long, value_name = "MILLISECONDS")]
+    health_interval_ms: Option<u64>,
+
+    /// Print a handshake line to stdout once the listener is bound.
+    #[arg(long)]
+    handshake: bool,
+
+    /// Append HandleRequest/HandleResponse calls to this JSONL file.
+    #[cfg(feature = "record")]
+    #[arg(long, value_name = "PATH")]
+    record: Option<PathBuf>,
+
+    /// Header to redact in recordings, in addition to credentials (repeatable).
+    #[cfg(feature = "record")]
+    #[arg(long = "redact-header", value_name = "NAME")]
+    redact_headers: Vec<String>,
+
+    /// Record inputs with their credentials, so recordings of plugins reading them replay.
+    #[cfg(feature = "record")]
+    #[arg(long)]
+    record_unredacted_inputs: bool,
+
+    /// Largest message in bytes the plugin accepts (default: 4 MiB).
+    #[arg(long, value_name = "BYTES")]
+    max_decoding_message_size: Option<usize>,
+
+    /// Largest message in bytes the plugin sends (default: unlimited).
+    #[arg(long, value_name = "BYTES")]
+    max_encoding_message_size: Option<usize>,
+
+    /// Comma-separated compression algorithms accepted from the host (gzip, zstd).
+    #[arg(long, value_enum, value_delimiter = ',')]
+    accept_compression: Vec<Compression>,
+
+    /// Compress responses with this algorithm when the host accepts it.
+    #[arg(long, value_enum)]
+    send_compression: Option<Compression>,
+
+    /// Comma-separated plugin protocol versions to serve (default: all compiled in).
+    #[arg(long, value_enum, value_delimiter = ',')]
+    protocol_versions: Vec<ProtocolVersion>,
+
+    /// Shut down gracefully when the parent process exits.
+    #[arg(long)]
+    exit_with_host: bool,
+
+    /// Shut down gracefully when this process exits (instead of the parent).
+    #[arg(long, value_name = "PID")]
+    host_pid: Option<i32>,
+}
+
+impl Args {
+    /// Returns the peer allowlist given on the command line, if any.
+    fn peer_credentials(&self) -> Option<PeerCredentials> {
+        let requested = self.verify_peer
+            || self.peer_parent
+            || !self.peer_uids.is_empty()
+            || !self.peer_gids.is_empty()
+            || !self.peer_pids.is_empty();
+        if !requested {
+            return None;
+        }
+
+        let mut peers = PeerCredentials::new();
+        for uid in &self.peer_uids {
+            peers = peers.allow_uid(*uid);
+        }
+        for gid in &self.peer_gids {
+            peers = peers.allow_gid(*gid);
+        }
+        for pid in &self.peer_pids {
+            peers = peers.allow_pid(*pid);
+        }
+        #[cfg(unix)]
+        if self.peer_parent {
+            peers = peers.allow_parent();
+        }
+        Some(peers)
+    }
+
+    /// Returns the host process to watch given on the command line, if any.
+    fn host_watch(&self) -> Option<HostWatch> {
+        match (self.host_pid, self.exit_with_host) {
+            (Some(pid), _) => Some(HostWatch::Pid(pid)),
+            (None, true) => Some(HostWatch::Parent),
+            (None, false) => None,
+        }
+    }
+}
+
+/// Options that apply however the plugin is listening.
+struct RunOptions {
+    peers: Option<PeerCredentials>,
+    host: Option<HostWatch>,
+    handshake: Option<Handshake>,
+}
+
+/// Builder for configuring how a plugin is served.
+///
+/// Options set here act as defaults; the equivalent command-line flags override them.
+/// [`serve()`] is shorthand for `ServerBuilder::new(plugin).serve(args)`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use mcpd_plugins_sdk::{Plugin, ServerBuilder, TimeoutPolicy};
+/// use std::time::Duration;
+///
+/// struct MyPlugin;
+///
+/// #[tonic::async_trait]
+/// impl Plugin for MyPlugin {}
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     ServerBuilder::new(MyPlugin)
+///         .rpc_timeout(Duration::from_millis(500))
+///         .timeout_policy(TimeoutPolicy::FailOpen)
+///         .serve(None)
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+pub struct ServerBuilder<P: Plugin> {
+    adapter: PluginAdapter<P>,
+    layers: Vec<BoxPluginLayer>,
+    max_in_flight: Option<usize>,
+    overload_policy: OverloadPolicy,
+    socket: UnixSocketOptions,
+    peers: Option<PeerCredentials>,
+    host: Option<HostWatch>,
+    health_interval: Duration,
+    handshake: bool,
+    protocol_versions: Vec<ProtocolVersion>,
+    codec: CodecOptions,
+}
+
+impl<P: Plugin> ServerBuilder<P> {
+    /// Creates a builder that serves `plugin` with default options.
+    pub fn new(plugin: P) -> Self {
+        watchdog::capture_parent();
+        Self {
+            adapter: PluginAdapter::new(plugin),
+            layers: Vec::new(),
+            max_in_flight: None,
+            overload_policy: OverloadPolicy::default(),
+            socket: UnixSocketOptions::default(),
+            peers: None,
+            host: None,
+            health_interval: health::DEFAULT_HEALTH_INTERVAL,
+            handshake: false,
+            protocol_versions: ProtocolVersion::ALL.to_vec(),
+            codec: CodecOptions::default(),
+        }
+    }
+
+    /// Sets the maximum time any single RPC may take (`--rpc-timeout-ms`).
+    ///
+    /// A shorter `grpc-timeout` sent by the host takes precedence. The plugin's deadline then
+    /// ends a tenth of the host's earlier (at least 5ms, at most 100ms), because tonic cancels
+    /// the whole call when the host's deadline expires, before the timeout policy could apply.
+    pub fn rpc_timeout(mut self, timeout: Duration) -> Self {
+        self.adapter = self.adapter.rpc_timeout(timeout);
+        self
+    }
+
+    /// Sets what request and response handling returns on timeout (`--timeout-policy`).
+    pub fn timeout_policy(mut self, policy: TimeoutPolicy) -> Self {
+        self.adapter = self.adapter.timeout_policy(policy);
+        self
+    }
+
+    /// Limits how many `HandleRequest`/`HandleResponse` calls run at once (`--max-in-flight`).
+    ///
+    /// While the limit is reached, `CheckReady` reports not-ready. A limit of 0 makes
+    /// [`serve`](Self::serve) fail with a configuration error.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = Some(max_in_flight);
+        self
+    }
+
+    /// Sets whether calls beyond the in-flight limit wait or are rejected
+    /// with `ResourceExhausted` (`--overload-policy`).
+    pub fn overload_policy(mut self, policy: OverloadPolicy) -> Self {
+        self.overload_policy = policy;
+        self
+    }
+
+    /// Sets the permission bits of the unix socket file, e.g. `0o660` (`--socket-mode`).
+    ///
+    /// Without it the socket is created according to the process umask.
+    pub fn socket_mode(mut self, mode: u32) -> Self {
+        self.socket.mode = Some(mode);
+        self
+    }
+
+    /// Sets the group, by name or numeric id, owning the unix socket file (`--socket-group`).
+    pub fn socket_group(mut self, group: impl Into<String>) -> Self {
+        self.socket.group = Some(group.into());
+        self
+    }
+
+    /// Only admits unix socket connections from peers on the allowlist (`--verify-peer`).
+    ///
+    /// Rejected connections are closed before any RPC runs and logged.
+    pub fn peer_credentials(mut self, peers: PeerCredentials) -> Self {
+        self.peers = Some(peers);
+        self
+    }
+
+    /// Sets how often `check_health` and `check_ready` are polled to update the standard
+    /// `grpc.health.v1.Health` service (`--health-interval-ms`). Defaults to 5 seconds.
+    pub fn health_interval(mut self, interval: Duration) -> Self {
+        self.health_interval = interval;
+        self
+    }
+
+    /// Prints a single handshake line to stdout once the listener is bound (`--handshake`).
+    ///
+    /// The line tells the host where to connect and that the plugin is ready, so it does
+    /// not have to poll the address. Log to stderr when using this, so stdout carries
+    /// nothing else.
+    pub fn handshake(mut self) -> Self {
+        self.handshake = true;
+        self
+    }
+
+    /// Appends every `HandleRequest`/`HandleResponse` call to `recorder` (`--record`).
+    #[cfg(feature = "record")]
+    pub fn record(mut self, recorder: Recorder) -> Self {
+        self.adapter = self.adapter.record(recorder);
+        self
+    }
+
+    /// Sets the plugin protocol versions to serve (`--protocol-versions`).
+    ///
+    /// Defaults to every version compiled in, see [`ProtocolVersion::ALL`]. Serving a version
+    /// whose `proto-<version>` feature is off is a configuration error. The served versions
+    /// are listed in `GetMetadata` responses and in the handshake line.
+    pub fn protocol_versions(
+        mut self,
+        versions: impl IntoIterator<Item = ProtocolVersion>,
+    ) -> Self {
+        self.protocol_versions = versions.into_iter().collect();
+        self
+    }
+
+    /// Sets the largest message in bytes the plugin accepts (`--max-decoding-message-size`).
+    ///
+    /// Defaults to tonic's 4 MiB. Raise it when `HandleResponse` receives large tool results;
+    /// larger messages are rejected before reaching the plugin, with a warning logged.
+    pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+        self.codec.max_decoding_message_size = Some(limit);
+        self
+    }
+
+    /// Sets the largest message in bytes the plugin sends (`--max-encoding-message-size`).
+    ///
+    /// Unlimited by default. `HandleRequest`/`HandleResponse` results over the limit fail
+    /// with `OutOfRange`, with a warning logged.
+    pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+        self.codec.max_encoding_message_size = Some(limit);
+        self
+    }
+
+    /// Accepts messages from the host compressed with `compression` (`--accept-compression`).
+    ///
+    /// Call once per algorithm. Each needs the SDK feature of the same name.
+    pub fn accept_compressed(mut self, compression: Compression) -> Self {
+        self.codec.accept_compressed.push(compression);
+        self
+    }
+
+    /// Compresses messages sent to the host with `compression`, when the host advertises
+    /// support for it (`--send-compression`).
+    ///
+    /// Needs the SDK feature of the same name.
+    pub fn send_compressed(mut self, compression: Compression) -> Self {
+        self.codec.send_compressed = Some(compression);
+        self
+    }
+
+    /// Shuts down gracefully, as on SIGTERM, when the parent process exits (`--exit-with-host`).
+    ///
+    /// On Linux the parent's death is signalled immediately; elsewhere it is noticed within
+    /// about a second.
+    pub fn exit_with_host(mut self) -> Self {
+        self.host = Some(HostWatch::Parent);
+        self
+    }
+
+    /// Shuts down gracefully when the process with id `pid` exits (`--host-pid`).
+    ///
+    /// Use this when the host starts the plugin through a launcher or wrapper, so the
+    /// plugin's parent is not the host itself.
+    pub fn host_pid(mut self, pid: i32) -> Self {
+        self.host = Some(HostWatch::Pid(pid));
+        self
+    }
+
+    /// Wraps the plugin gRPC service in a tower [`Layer`].
+    ///
+    /// Layers are applied in the order they are added, the first one being outermost, and
+    /// see the service as a [`BoxPluginService`]. Errors returned by a layer are sent to the
+    /// host as gRPC statuses.
+    pub fn layer<L, S, B>(mut self, layer: L) -> Self
+    where
+        L: Layer<BoxPluginService, Service = S> + Send + 'static,
+        S: Service<http::Request<BoxBody>, Response = http::Response<B>> + Clone + Send + 'static,
+        S::Future: Send + 'static,
+        S::Error: Into<BoxError>,
+        B: http_body::Body<Data = bytes::Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        self.layers.push(layer::boxed(layer));
+        self
+    }
+
+    /// Adds plugin-level [`Middleware`] around `handle_request` and `handle_response`.
+    ///
+    /// Middleware runs in the order it is added, the first one being outermost.
+    pub fn middleware<M: Middleware>(mut self, middleware: M) -> Self {
+        self.adapter = self.adapter.middleware(middleware);
+        self
+    }
+
+    /// Returns the counters of the adapter that will serve the plugin.
+    pub fn stats(&self) -> Arc<AdapterStats> {
+        self.adapter.stats()
+    }
+
+    /// Serves the plugin on the address given on the command line.
+    ///
+    /// See [`serve()`] for details.
+    pub async fn serve(self, args: Option<Vec<String>>) -> Result<()> {
+        // Parse command-line arguments.
+        let args = if let Some(args) = args {
+            Args::parse_from(args)
+        } else {
+            Args::parse()
+        };
+
+        info!(
+            "Starting plugin server on {} ({})",
+            args.address, args.network
+        );
+
+        // Apply command-line overrides to the plugin adapter.
+        let mut adapter = self.adapter;
+        if let Some(timeout_ms) = args.rpc_timeout_ms {
+            adapter = adapter.rpc_timeout(Duration::from_millis(timeout_ms));
+        }
+        if let Some(policy) = args.timeout_policy {
+            adapter = adapter.timeout_policy(policy);
+        }
+        #[cfg(feature = "record")]
+        if let Some(path) = &args.record {
+            let mut recorder = args
+                .redact_headers
+                .iter()
+                .fold(Recorder::create(path)?, |recorder, name| {
+                    recorder.redact_header(name.clone())
+                });
+            if args.record_unredacted_inputs {
+                recorder = recorder.unredacted_inputs();
+            }
+            adapter = adapter.record(recorder);
+        }
+        let overload_policy = args.overload_policy.unwrap_or(self.overload_policy);
+        if let Some(max_in_flight) = args.max_in_flight.or(self.max_in_flight) {
+            let max_in_flight = NonZeroUsize::new(max_in_flight).ok_or_else(|| {
+                PluginError::Configuration("max-in-flight must be greater than zero".to_string())
+            })?;
+            adapter = adapter.max_in_flight(max_in_flight, overload_policy);
+        }
+
+        let mut socket = self.socket;
+        if let Some(mode) = args.socket_mode {
+            socket.mode = Some(mode);
+        }
+        if let Some(group) = args.socket_group.clone() {
+            socket.group = Some(group);
+        }
+        let peers = args.peer_credentials().or(self.peers);
+        let host = args.host_watch().or(self.host);
+        if cfg!(not(unix)) && host.is_some() {
+            return Err(PluginError::Configuration(
+                "Watching the host process is not supported on this platform".to_string(),
+            ));
+        }
+
+        let health_interval = args
+            .health_interval_ms
+            .map(Duration::from_millis)
+            .unwrap_or(self.health_interval);
+        if health_interval.is_zero() {
+            return Err(PluginError::Configuration(
+                "health-interval-ms must be greater than zero".to_string(),
+            ));
+        }
+
+        let mut protocol_versions = if args.protocol_versions.is_empty() {
+            self.protocol_versions
+        } else {
+            args.protocol_versions.clone()
+        };
+        protocol_versions.sort();
+        protocol_versions.dedup();
+        if protocol_versions.is_empty() {
+            return Err(PluginError::Configuration(
+                "at least one protocol version must be served".to_string(),
+            ));
+        }
+        for version in &protocol_versions {
+            version.check_compiled()?;
+        }
+        let adapter = adapter.protocol_versions(protocol_versions.clone());
+
+        let mut codec = self.codec;
+        if let Some(limit) = args.max_decoding_message_size {
+            codec.max_decoding_message_size = Some(limit);
+        }
+        if let Some(limit) = args.max_encoding_message_size {
+            codec.max_encoding_message_size = Some(limit);
+        }
+        if !args.accept_compression.is_empty() {
+            codec.accept_compressed = args.accept_compression.clone();
+        }
+        if let Some(compression) = args.send_compression {
+            codec.send_compressed = Some(compression);
+        }
+        codec.validate()?;
+        let adapter = adapter.max_encoding_message_size(codec.max_encoding_message_size);
+
+        // Mirror the plugin's health and readiness checks on the standard health service.
+        let adapter = Arc::new(adapter);
+        let (reporter, health_service) = tonic_health::server::health_reporter();
+        let health_sync =
+            tokio::spawn(health::sync(adapter.clone(), reporter, health_interval)).abort_handle();
+
+        // Read the plugin's name and version for the handshake line before listening.
+        let handshake = if args.handshake || self.handshake {
+            Some(Handshake::from_metadata(adapter.as_ref(), &protocol_versions).await)
+        } else {
+            None
+        };
+
+        // Wrap the service in any tower layers, outermost first.
+        let server = codec.apply(PluginServer::from_arc(adapter))?;
+        let service = self.layers.into_iter().rev().fold(
+            BoxPluginService::new(LogOversized::new(server, codec.max_decoding_message_size)),
+            |service, layer| layer(service),
+        );
+
+        // Serve the plugin under each protocol version. Versions other than v1 are served
+        // by adapters translating to and from the v1 service.
+        let mut router = Server::builder().add_service(health_service);
+        for version in &protocol_versions {
+            router = version.add_service(router, service.clone())?;
+        }
+        #[cfg(feature = "reflection")]
+        let router = add_reflection(router, &protocol_versions)?;
+
+        let run = RunOptions {
+            peers,
+            host,
+            handshake,
+        };
+        let result = serve_router(router, &args, &socket, run).await;
+        health_sync.abort();
+        result
+    }
 }
 
 /// Serves a plugin on the specified address.
 ///
 /// This is the main entry point for running a plugin. It handles:
 /// - Command-line argument parsing
-/// - Server setup (Unix socket or TCP)
-/// - Graceful shutdown on SIGINT/SIGTERM
+/// - Server setup (Unix socket, TCP, or an inherited listening socket)
+/// - Graceful shutdown on SIGINT/SIGTERM, or when the host exits if requested
 /// - Automatic cleanup of Unix socket files
 ///
+/// Use [`ServerBuilder`] to change the defaults for options such as RPC timeouts.
+///
 /// # Arguments
 ///
 /// * `plugin` - The plugin implementation to serve
@@ -55,26 +601,53 @@ struct Args {
 /// }
 /// ```
 pub async fn serve<P: Plugin>(plugin: P, args: Option<Vec<String>>) -> Result<()> {
-    // Parse command-line arguments.
-    let args = if let Some(args) = args {
-        Args::parse_from(args)
-    } else {
-        Args::parse()
+    ServerBuilder::new(plugin).serve(args).await
+}
+
+/// Registers gRPC server reflection (v1 and v1alpha) for the health service and the plugin
+/// service of each served protocol version.
+#[cfg(feature = "reflection")]
+fn add_reflection(router: Router, versions: &[ProtocolVersion]) -> Result<Router> {
+    let builder = || {
+        versions.iter().fold(
+            tonic_reflection::server::Builder::configure()
+                .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET),
+            |builder, version| {
+                builder.register_encoded_file_descriptor_set(version.file_descriptor_set())
+            },
+        )
     };
+    let reflection_error =
+        |err: tonic_reflection::server::Error| PluginError::Server(format!("reflection: {}", err));
 
-    info!(
-        "Starting plugin server on {} ({})",
-        args.address, args.network
-    );
+    let v1 = builder().build_v1().map_err(reflection_error)?;
+    let v1alpha = builder().build_v1alpha().map_err(reflection_error)?;
+    Ok(router.add_service(v1).add_service(v1alpha))
+}
 
-    // Create the plugin adapter.
-    let adapter = PluginAdapter::new(plugin);
-    let service = PluginServer::new(adapter);
+/// Serves `router` on the listener selected by the command-line arguments.
+async fn serve_router(
+    router: Router,
+    args: &Args,
+    socket: &UnixSocketOptions,
+    run: RunOptions,
+) -> Result<()> {
+    // Serve on a listening socket passed in by the parent process, if there is one.
+    #[cfg(unix)]
+    if let Some(fd) = activation::inherited_fd(&args.network, &args.address)? {
+        return serve_fd(router, fd, &args.address, socket, run).await;
+    }
 
     // Serve based on network type.
     match args.network.as_str() {
-        "unix" => serve_unix(service, &args.address).await,
-        "tcp" => serve_tcp(service, &args.address).await,
+        "unix" => serve_unix(router, &args.address, socket, run).await,
+        "tcp" if run.peers.is_some() => Err(PluginError::Configuration(
+            "Peer credential checks require a unix socket".to_string(),
+        )),
+        "tcp" => serve_tcp(router, &args.address, run).await,
+        "fd" => Err(PluginError::Configuration(
+            "Inherited file descriptors are not supported on this platform".to_string(),
+        )),
         network => Err(PluginError::Configuration(format!(
             "Unsupported network type: {}",
             network
@@ -83,42 +656,40 @@ pub async fn serve<P: Plugin>(plugin: P, args: Option<Vec<String>>) -> Result<()
 }
 
 #[cfg(unix)]
-async fn serve_unix<S>(service: S, address: &str) -> Result<()>
-where
-    S: tonic::codegen::Service<
-            http::Request<tonic::body::BoxBody>,
-            Response = http::Response<tonic::body::BoxBody>,
-            Error = std::convert::Infallible,
-        > + tonic::server::NamedService
-        + Clone
-        + Send
-        + 'static,
-    S::Future: Send + 'static,
-{
-    use tokio_stream::wrappers::UnixListenerStream;
-
-    let path = PathBuf::from(address);
+async fn serve_unix(
+    router: Router,
+    address: &str,
+    options: &UnixSocketOptions,
+    run: RunOptions,
+) -> Result<()> {
+    // Abstract sockets ("@name") have no file to prepare, secure or clean up.
+    let (listener, path) = match address.strip_prefix('@') {
+        Some(name) => {
+            if options.mode.is_some() || options.group.is_some() {
+                warn!(
+                    "Socket mode and group do not apply to abstract socket {}",
+                    address
+                );
+            }
+            (socket::bind_abstract(name)?, None)
+        }
+        None => {
+            let path = PathBuf::from(address);
 
-    // Remove existing socket file if it exists.
-    if path.exists() {
-        warn!("Removing existing socket file: {}", address);
-        std::fs::remove_file(&path)?;
-    }
+            // Create the parent directory or remove a stale socket file, as needed.
+            socket::prepare_path(&path, options)?;
 
-    // Create Unix listener.
-    let listener = UnixListener::bind(&path)?;
-    let stream = UnixListenerStream::new(listener);
+            // Create Unix listener, with its mode and group set before anyone can connect.
+            let listener = socket::bind_secured(&path, options)?;
+            (listener, Some(path))
+        }
+    };
 
     info!("Listening on Unix socket: {}", address);
-
-    // Serve with graceful shutdown.
-    Server::builder()
-        .add_service(service)
-        .serve_with_incoming_shutdown(stream, shutdown_signal())
-        .await?;
+    serve_unix_listener(router, listener, address.to_string(), run).await?;
 
     // Clean up socket file on shutdown.
-    if path.exists() {
+    if let Some(path) = path.filter(|path| path.exists()) {
         info!("Cleaning up socket file: {}", address);
         let _ = std::fs::remove_file(&path);
     }
@@ -126,53 +697,115 @@ where
     Ok(())
 }
 
+/// Serves on a bound unix listener until a shutdown signal arrives.
+///
+/// `address` is announced in the handshake.
+#[cfg(unix)]
+async fn serve_unix_listener(
+    router: Router,
+    listener: UnixListener,
+    address: String,
+    run: RunOptions,
+) -> Result<()> {
+    use tokio_stream::wrappers::UnixListenerStream;
+    use tokio_stream::StreamExt;
+
+    if let Some(handshake) = &run.handshake {
+        handshake.announce("unix", &address)?;
+    }
+
+    // Drop connections from peers that are not allowlisted before they reach the server.
+    let peers = run.peers.map(PeerCredentials::or_current_user);
+    let stream = UnixListenerStream::new(listener).filter(move |conn| match (&peers, conn) {
+        (Some(peers), Ok(stream)) => peers.admits(stream),
+        _ => true,
+    });
+
+    // Serve with graceful shutdown.
+    router
+        .serve_with_incoming_shutdown(stream, shutdown_signal(run.host))
+        .await?;
+
+    Ok(())
+}
+
+/// Serves on a listening socket inherited from the parent process.
+///
+/// The socket belongs to whoever created it, so it is neither secured nor removed here.
+#[cfg(unix)]
+async fn serve_fd(
+    router: Router,
+    fd: RawFd,
+    address: &str,
+    options: &UnixSocketOptions,
+    run: RunOptions,
+) -> Result<()> {
+    match activation::listener_from_fd(fd)? {
+        InheritedListener::Unix(listener) => {
+            if options.mode.is_some() || options.group.is_some() {
+                warn!("Socket mode and group do not apply to inherited fd {}", fd);
+            }
+            info!("Listening on inherited Unix socket (fd {})", fd);
+            // Announce the path the socket is bound to, if it has one.
+            let address = match listener.local_addr()?.as_pathname() {
+                Some(path) => path.display().to_string(),
+                None => address.to_string(),
+            };
+            serve_unix_listener(router, listener, address, run).await
+        }
+        InheritedListener::Tcp(_) if run.peers.is_some() => Err(PluginError::Configuration(
+            "Peer credential checks require a unix socket".to_string(),
+        )),
+        InheritedListener::Tcp(listener) => {
+            info!("Listening on inherited TCP socket (fd {})", fd);
+            serve_tcp_listener(router, listener, run).await
+        }
+    }
+}
+
 #[cfg(not(unix))]
-async fn serve_unix<S>(_service: S, _address: &str) -> Result<()>
-where
-    S: tonic::codegen::Service<
-            http::Request<tonic::body::BoxBody>,
-            Response = http::Response<tonic::body::BoxBody>,
-            Error = std::convert::Infallible,
-        > + tonic::server::NamedService
-        + Clone
-        + Send
-        + 'static,
-    S::Future: Send + 'static,
-{
+async fn serve_unix(
+    _router: Router,
+    _address: &str,
+    _options: &UnixSocketOptions,
+    _run: RunOptions,
+) -> Result<()> {
     Err(PluginError::Configuration(
         "Unix sockets not supported on this platform".to_string(),
     ))
 }
 
-async fn serve_tcp<S>(service: S, address: &str) -> Result<()>
-where
-    S: tonic::codegen::Service<
-            http::Request<tonic::body::BoxBody>,
-            Response = http::Response<tonic::body::BoxBody>,
-            Error = std::convert::Infallible,
-        > + tonic::server::NamedService
-        + Clone
-        + Send
-        + 'static,
-    S::Future: Send + 'static,
-{
-    let addr = address
+async fn serve_tcp(router: Router, address: &str, run: RunOptions) -> Result<()> {
+    let addr: std::net::SocketAddr = address
         .parse()
         .map_err(|e| PluginError::Configuration(format!("Invalid TCP address: {}", e)))?;
 
-    info!("Listening on TCP: {}", address);
+    let listener = TcpListener::bind(addr).await?;
+    info!("Listening on TCP: {}", listener.local_addr()?);
+
+    serve_tcp_listener(router, listener, run).await
+}
+
+/// Serves on a bound TCP listener until a shutdown signal arrives.
+async fn serve_tcp_listener(router: Router, listener: TcpListener, run: RunOptions) -> Result<()> {
+    if let Some(handshake) = &run.handshake {
+        handshake.announce("tcp", &listener.local_addr()?.to_string())?;
+    }
+
+    // Match the socket options tonic applies when it binds the address itself.
+    let incoming = TcpIncoming::from_listener(listener, true, None)
+        .map_err(|e| PluginError::Server(e.to_string()))?;
 
     // Serve with graceful shutdown.
-    Server::builder()
-        .add_service(service)
-        .serve_with_shutdown(addr, shutdown_signal())
+    router
+        .serve_with_incoming_shutdown(incoming, shutdown_signal(run.host))
         .await?;
 
     Ok(())
 }
 
-/// Waits for a shutdown signal (SIGINT or SIGTERM).
-async fn shutdown_signal() {
+/// Waits for a shutdown signal (SIGINT or SIGTERM) or, if watched, the host's exit.
+async fn shutdown_signal(host: Option<HostWatch>) {
     let ctrl_c = async {
         signal::ctrl_c()
             .await
@@ -190,12 +823,25 @@ async fn shutdown_signal() {
     #[cfg(not(unix))]
     let terminate = std::future::pending::<()>();
 
+    let host_exited = async {
+        match host {
+            Some(host) => watchdog::host_exited(host).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    // Poll in order so the SIGTERM handler is installed before the watchdog arms the
+    // parent death signal.
     tokio::select! {
+        biased;
         _ = ctrl_c => {
             info!("Received SIGINT, shutting down gracefully");
         }
         _ = terminate => {
             info!("Received SIGTERM, shutting down gracefully");
         }
+        _ = host_exited => {
+            info!("Host process exited, shutting down gracefully");
+        }
     }
 }
@@ -0,0 +1,414 @@
+#[cfg(unix)]
+use crate::{PluginError, Result};
+#[cfg(unix)]
+use hyper_util::rt::TokioIo;
+#[cfg(unix)]
+use std::path::Path;
+#[cfg(unix)]
+use tracing::{info, warn};
+
+/// Permissions and ownership applied to a Unix socket and its parent directory.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(not(unix), allow(dead_code))]
+pub(crate) struct UnixSocketOptions {
+    /// Permission bits for the socket file; the process umask applies when unset.
+    pub(crate) mode: Option<u32>,
+
+    /// Group (name or numeric id) owning the socket file.
+    pub(crate) group: Option<String>,
+}
+
+/// Parses an octal permission mode such as `660` or `0o660`.
+pub(crate) fn parse_mode(value: &str) -> std::result::Result<u32, String> {
+    let digits = value.strip_prefix("0o").unwrap_or(value);
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if mode <= 0o777 => Ok(mode),
+        _ => Err(format!(
+            "invalid socket mode {:?}, expected octal permissions such as 660",
+            value
+        )),
+    }
+}
+
+/// Prepares `path` for binding a new socket.
+///
+/// Missing parent directories are created accessible to the owner only (plus read and
+/// traverse for the socket group, if set), each of them and not just the innermost one.
+/// An existing socket is removed only if nothing is listening on it any more; anything
+/// else at `path` is left alone and reported.
+#[cfg(unix)]
+pub(crate) fn prepare_path(path: &Path, options: &UnixSocketOptions) -> Result<()> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        let missing: Vec<&Path> = parent
+            .ancestors()
+            .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+            .collect();
+        let mode = if options.group.is_some() {
+            0o750
+        } else {
+            0o700
+        };
+        let gid = options.group.as_deref().map(resolve_group).transpose()?;
+
+        // Outermost first; the mode is set explicitly since the umask applies on creation.
+        for dir in missing.into_iter().rev() {
+            info!(
+                "Creating socket directory {} (mode {:o})",
+                dir.display(),
+                mode
+            );
+            std::fs::create_dir(dir)?;
+            std::fs::set_permissions(dir, std::fs::Permissions::from_mode(mode))?;
+            if let Some(gid) = gid {
+                std::os::unix::fs::chown(dir, None, Some(gid))?;
+            }
+        }
+    }
+
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+
+    if !metadata.file_type().is_socket() {
+        return Err(PluginError::Configuration(format!(
+            "{} already exists and is not a socket, refusing to remove it",
+            path.display()
+        )));
+    }
+
+    // A successful connection means another process is still serving on this socket.
+    match std::os::unix::net::UnixStream::connect(path) {
+        Ok(_) => Err(PluginError::Configuration(format!(
+            "{} is in use by another process",
+            path.display()
+        ))),
+        Err(err) if err.kind() == std::io::ErrorKind::ConnectionRefused => {
+            warn!("Removing stale socket file: {}", path.display());
+            std::fs::remove_file(path)?;
+            Ok(())
+        }
+        Err(err) => Err(PluginError::Configuration(format!(
+            "cannot determine whether {} is in use: {}",
+            path.display(),
+            err
+        ))),
+    }
+}
+
+/// Binds a socket at `path` with the configured mode and group already applied.
+///
+/// A socket is created with permissions derived from the umask and accepts connections as
+/// soon as it is bound, so it is bound inside a private (0700) staging directory next to
+/// `path`, secured there and only then linked into place. Linking fails if anything was
+/// created at `path` since [`prepare_path`] checked it, rather than replacing it.
+#[cfg(unix)]
+pub(crate) fn bind_secured(
+    path: &Path,
+    options: &UnixSocketOptions,
+) -> Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    let name = path.file_name().ok_or_else(|| {
+        PluginError::Configuration(format!("{} is not a socket file path", path.display()))
+    })?;
+    let mut staging_name = std::ffi::OsString::from(".");
+    staging_name.push(name);
+    staging_name.push(format!(".{}.tmp", std::process::id()));
+    let staging = path.with_file_name(staging_name);
+
+    let mut builder = std::fs::DirBuilder::new();
+    builder.mode(0o700);
+    if let Err(err) = builder.create(&staging) {
+        if err.kind() != std::io::ErrorKind::AlreadyExists {
+            return Err(err.into());
+        }
+        // Left over by a crashed process that had our pid, e.g. an earlier run as PID 1.
+        warn!("Removing leftover staging directory: {}", staging.display());
+        std::fs::remove_dir_all(&staging)?;
+        builder.create(&staging)?;
+    }
+    let staged = staging.join(name);
+    let result = bind_staged(&staged, path, options);
+    let _ = std::fs::remove_file(&staged);
+    let _ = std::fs::remove_dir(&staging);
+    result
+}
+
+#[cfg(unix)]
+fn bind_staged(
+    staged: &Path,
+    path: &Path,
+    options: &UnixSocketOptions,
+) -> Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let listener = tokio::net::UnixListener::bind(staged)?;
+    if let Some(group) = &options.group {
+        std::os::unix::fs::chown(staged, None, Some(resolve_group(group)?))?;
+    }
+    if let Some(mode) = options.mode {
+        std::fs::set_permissions(staged, std::fs::Permissions::from_mode(mode))?;
+    }
+    // Unlike a rename, a link never replaces whatever is at `path`.
+    std::fs::hard_link(staged, path).map_err(|err| {
+        if err.kind() == std::io::ErrorKind::AlreadyExists {
+            PluginError::Configuration(format!(
+                "{} was created by another process while binding",
+                path.display()
+            ))
+        } else {
+            err.into()
+        }
+    })?;
+    Ok(listener)
+}
+
+/// Binds a Linux abstract-namespace socket, which has no filesystem presence and is
+/// released by the kernel when the process exits.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn bind_abstract(name: &str) -> Result<tokio::net::UnixListener> {
+    let listener = std::os::unix::net::UnixListener::bind_addr(&abstract_addr(name)?)?;
+    listener.set_nonblocking(true)?;
+    Ok(tokio::net::UnixListener::from_std(listener)?)
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+pub(crate) fn bind_abstract(_name: &str) -> Result<tokio::net::UnixListener> {
+    Err(PluginError::Configuration(
+        "Abstract unix sockets are only supported on Linux".to_string(),
+    ))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn abstract_addr(name: &str) -> Result<std::os::unix::net::SocketAddr> {
+    #[cfg(target_os = "android")]
+    use std::os::android::net::SocketAddrExt;
+    #[cfg(target_os = "linux")]
+    use std::os::linux::net::SocketAddrExt;
+
+    if name.is_empty() {
+        return Err(PluginError::Configuration(
+            "Abstract socket name must not be empty".to_string(),
+        ));
+    }
+    Ok(std::os::unix::net::SocketAddr::from_abstract_name(name)?)
+}
+
+/// Opens a gRPC channel to a plugin listening on a unix socket.
+///
+/// `address` is either a socket path or, on Linux, `@name` for an abstract-namespace socket,
+/// matching the `--address` accepted by [`serve()`](crate::serve). This is the client-side
+/// counterpart for hosts, tools and tests written in Rust.
+///
+/// ```rust,no_run
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let channel = mcpd_plugins_sdk::connect_unix("@my-plugin").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(unix)]
+pub async fn connect_unix(address: &str) -> Result<tonic::transport::Channel> {
+    let address = address.to_string();
+
+    // The URI is required by tonic but ignored by the connector.
+    let channel = tonic::transport::Endpoint::from_static("http://[::]:50051")
+        .connect_with_connector(tower::service_fn(move |_: tonic::transport::Uri| {
+            let address = address.clone();
+            async move { connect_stream(&address).await.map(TokioIo::new) }
+        }))
+        .await?;
+    Ok(channel)
+}
+
+/// Connects a stream to a socket path or `@name` abstract address.
+#[cfg(unix)]
+async fn connect_stream(address: &str) -> std::io::Result<tokio::net::UnixStream> {
+    match address.strip_prefix('@') {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        Some(name) => {
+            let addr = abstract_addr(name).map_err(std::io::Error::other)?;
+            let stream = std::os::unix::net::UnixStream::connect_addr(&addr)?;
+            stream.set_nonblocking(true)?;
+            tokio::net::UnixStream::from_std(stream)
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        Some(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "abstract unix sockets are only supported on Linux",
+        )),
+        None => tokio::net::UnixStream::connect(address).await,
+    }
+}
+
+/// Resolves a group name or numeric id to a group id.
+#[cfg(unix)]
+fn resolve_group(group: &str) -> Result<u32> {
+    if let Ok(gid) = group.parse() {
+        return Ok(gid);
+    }
+
+    let name = std::ffi::CString::new(group)
+        .map_err(|_| PluginError::Configuration(format!("invalid group name {:?}", group)))?;
+    let mut buffer = vec![0 as libc::c_char; 1024];
+
+    loop {
+        let mut entry: libc::group = unsafe { std::mem::zeroed() };
+        let mut result: *mut libc::group = std::ptr::null_mut();
+
+        // SAFETY: every pointer refers to a live, correctly sized local for the whole call.
+        let rc = unsafe {
+            libc::getgrnam_r(
+                name.as_ptr(),
+                &mut entry,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            )
+        };
+
+        match rc {
+            0 if result.is_null() => {
+                return Err(PluginError::Configuration(format!(
+                    "unknown group {:?}",
+                    group
+                )))
+            }
+            0 => return Ok(entry.gr_gid),
+            libc::ERANGE => buffer.resize(buffer.len() * 2, 0),
+            errno => return Err(std::io::Error::from_raw_os_error(errno).into()),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    use std::path::PathBuf;
+
+    /// Returns a fresh directory path under the temporary directory, removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("mcpd-socket-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn mode(path: &Path) -> u32 {
+        std::fs::metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    #[test]
+    fn parses_modes() {
+        assert_eq!(parse_mode("660"), Ok(0o660));
+        assert_eq!(parse_mode("0o600"), Ok(0o600));
+        assert!(parse_mode("800").is_err());
+        assert!(parse_mode("1777").is_err());
+    }
+
+    #[test]
+    fn creates_every_missing_parent_with_mode_and_group() {
+        let root = TempDir::new("parents");
+        let path = root.0.join("a/b/plugin.sock");
+        // SAFETY: getegid has no preconditions.
+        let gid = unsafe { libc::getegid() };
+        let options = UnixSocketOptions {
+            mode: None,
+            group: Some(gid.to_string()),
+        };
+
+        prepare_path(&path, &options).unwrap();
+        for dir in [root.0.clone(), root.0.join("a"), root.0.join("a/b")] {
+            assert_eq!(mode(&dir), 0o750, "{}", dir.display());
+            assert_eq!(std::fs::metadata(&dir).unwrap().gid(), gid);
+        }
+    }
+
+    #[tokio::test]
+    async fn binds_with_mode_applied_and_no_staging_left() {
+        let root = TempDir::new("bind");
+        std::fs::create_dir(&root.0).unwrap();
+        let path = root.0.join("plugin.sock");
+        let options = UnixSocketOptions {
+            mode: Some(0o600),
+            group: None,
+        };
+
+        let _listener = bind_secured(&path, &options).unwrap();
+        assert_eq!(mode(&path), 0o600);
+        assert_eq!(std::fs::read_dir(&root.0).unwrap().count(), 1);
+        tokio::net::UnixStream::connect(&path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn refuses_a_live_socket() {
+        let root = TempDir::new("live");
+        std::fs::create_dir(&root.0).unwrap();
+        let path = root.0.join("plugin.sock");
+        let options = UnixSocketOptions::default();
+
+        let listener = bind_secured(&path, &options).unwrap();
+        assert!(prepare_path(&path, &options).is_err());
+
+        drop(listener);
+        prepare_path(&path, &options).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn never_replaces_what_appeared_at_the_path() {
+        let root = TempDir::new("appeared");
+        std::fs::create_dir(&root.0).unwrap();
+        let path = root.0.join("plugin.sock");
+        let options = UnixSocketOptions::default();
+
+        // Another instance bound the path after it was checked.
+        let _other = tokio::net::UnixListener::bind(&path).unwrap();
+        let result = bind_secured(&path, &options);
+        assert!(
+            matches!(&result, Err(PluginError::Configuration(message)) if message.contains("another process")),
+            "{:?}",
+            result.err()
+        );
+        tokio::net::UnixStream::connect(&path).await.unwrap();
+
+        // A regular file created after the check.
+        let file = root.0.join("file.sock");
+        std::fs::write(&file, "keep").unwrap();
+        assert!(bind_secured(&file, &options).is_err());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep");
+
+        // Neither attempt leaves a staging directory behind.
+        assert_eq!(std::fs::read_dir(&root.0).unwrap().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn removes_a_leftover_staging_directory() {
+        let root = TempDir::new("leftover");
+        std::fs::create_dir(&root.0).unwrap();
+        let path = root.0.join("plugin.sock");
+        let staging = root
+            .0
+            .join(format!(".plugin.sock.{}.tmp", std::process::id()));
+        std::fs::create_dir(&staging).unwrap();
+        std::fs::write(staging.join("plugin.sock"), "").unwrap();
+
+        let _listener = bind_secured(&path, &UnixSocketOptions::default()).unwrap();
+        assert!(!staging.exists());
+        tokio::net::UnixStream::connect(&path).await.unwrap();
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counters describing how the adapter has handled RPCs.
+///
+/// Obtain a shared handle from [`PluginAdapter::stats()`](crate::PluginAdapter::stats) or
+/// [`ServerBuilder::stats()`](crate::ServerBuilder::stats) and read it from anywhere,
+/// e.g. to export the values as metrics.
+#[derive(Debug, Default)]
+pub struct AdapterStats {
+    timeouts_fail_open: AtomicU64,
+    timeouts_fail_closed: AtomicU64,
+    requests_shed: AtomicU64,
+}
+
+impl AdapterStats {
+    /// Number of RPCs that exceeded their deadline and were answered with a pass-through response.
+    pub fn timeouts_fail_open(&self) -> u64 {
+        self.timeouts_fail_open.load(Ordering::Relaxed)
+    }
+
+    /// Number of RPCs that exceeded their deadline and were answered with `DeadlineExceeded`.
+    pub fn timeouts_fail_closed(&self) -> u64 {
+        self.timeouts_fail_closed.load(Ordering::Relaxed)
+    }
+
+    /// Number of request/response calls rejected with `ResourceExhausted` by the in-flight limit.
+    pub fn requests_shed(&self) -> u64 {
+        self.requests_shed.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_timeout_fail_open(&self) {
+        self.timeouts_fail_open.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_timeout_fail_closed(&self) {
+        self.timeouts_fail_closed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_shed(&self) {
+        self.requests_shed.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,487 @@
+//! [`proptest`] strategies for the proto message types.
+//!
+//! Every message implements [`Arbitrary`], so `any::<HttpRequest>()` works out of the box.
+//! [`Shape`] tunes how realistic HTTP messages look: methods, paths, headers and bodies
+//! follow the HTTP rules by default, and bodies can be MCP JSON-RPC messages or large.
+//!
+//! ```rust,no_run
+//! use mcpd_plugins_sdk::strategy::{self, Shape};
+//! use proptest::prelude::*;
+//!
+//! proptest! {
+//!     #[test]
+//!     fn keeps_unrelated_headers(request in strategy::http_request(Shape::default())) {
+//!         // Call the plugin and check the invariant.
+//!     }
+//! }
+//! ```
+
+use crate::proto::{
+    Capabilities, Flow, HttpRequest, HttpResponse, Metadata, PluginConfig, TelemetryConfig,
+};
+use bytes::Bytes;
+use proptest::arbitrary::Arbitrary;
+use proptest::collection::{hash_map, vec};
+use proptest::prelude::*;
+use proptest::sample::select;
+use std::collections::HashMap;
+
+/// Standard HTTP methods.
+const METHODS: &[&str] = &[
+    "GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH",
+];
+
+/// Header names commonly seen by mcpd plugins, mixed with generated ones.
+const COMMON_HEADERS: &[&str] = &[
+    "Accept",
+    "Authorization",
+    "Content-Type",
+    "Content-Length",
+    "Cookie",
+    "Mcp-Session-Id",
+    "User-Agent",
+    "X-Forwarded-For",
+    "X-Request-Id",
+];
+
+/// MCP JSON-RPC methods used for generated bodies.
+const MCP_METHODS: &[&str] = &[
+    "initialize",
+    "notifications/initialized",
+    "ping",
+    "tools/list",
+    "tools/call",
+    "resources/list",
+    "resources/read",
+    "prompts/list",
+    "prompts/get",
+];
+
+/// Knobs for the shape of generated HTTP messages.
+#[derive(Debug, Clone)]
+pub struct Shape {
+    /// Only generate standard methods, valid header names (unique ignoring case) and values,
+    /// decoded paths and URLs consistent with them. When false, those fields are arbitrary
+    /// strings.
+    pub valid_http: bool,
+
+    /// Maximum number of headers.
+    pub max_headers: usize,
+
+    /// Maximum size of arbitrary bodies, in bytes.
+    pub max_body: usize,
+
+    /// Also generate MCP JSON-RPC request and response bodies.
+    pub mcp_bodies: bool,
+
+    /// Also generate bodies between 64 KiB and `large_body` bytes.
+    pub large_body: Option<usize>,
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Self {
+            valid_http: true,
+            max_headers: 8,
+            max_body: 1024,
+            mcp_bodies: true,
+            large_body: None,
+        }
+    }
+}
+
+impl Shape {
+    /// Also generates bodies of up to `max` bytes (at least 64 KiB).
+    pub fn large_bodies(mut self, max: usize) -> Self {
+        self.large_body = Some(max.max(LARGE_BODY_MIN + 1));
+        self
+    }
+
+    /// Generates arbitrary strings where HTTP rules would apply, to test robustness.
+    pub fn invalid_http(mut self) -> Self {
+        self.valid_http = false;
+        self
+    }
+}
+
+/// Smallest size of a large body.
+const LARGE_BODY_MIN: usize = 64 * 1024;
+
+/// Standard HTTP methods.
+pub fn method() -> impl Strategy<Value = String> {
+    select(METHODS).prop_map(str::to_string)
+}
+
+/// Header names following the HTTP token rules, favouring common names.
+pub fn header_name() -> impl Strategy<Value = String> {
+    prop_oneof![
+        select(COMMON_HEADERS).prop_map(str::to_string),
+        "[A-Za-z][A-Za-z0-9-]{0,15}",
+        "[!#$%&'*+.^_`|~0-9A-Za-z-]{1,24}",
+    ]
+}
+
+/// Header values of visible ASCII and inner spaces, without CR or LF.
+pub fn header_value() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[!-~]([ !-~]{0,62}[!-~])?",
+        Just(String::new()),
+        "Bearer [A-Za-z0-9._-]{8,40}",
+    ]
+}
+
+/// Up to `shape.max_headers` headers. Names are unique ignoring case, since header names
+/// are case-insensitive and the proto keeps one value per name.
+pub fn headers(shape: &Shape) -> BoxedStrategy<HashMap<String, String>> {
+    if shape.valid_http {
+        vec((header_name(), header_value()), 0..=shape.max_headers)
+            .prop_map(|pairs| {
+                let mut headers = HashMap::new();
+                for (name, value) in pairs {
+                    if !headers
+                        .keys()
+                        .any(|existing: &String| existing.eq_ignore_ascii_case(&name))
+                    {
+                        headers.insert(name, value);
+                    }
+                }
+                headers
+            })
+            .boxed()
+    } else {
+        hash_map(any::<String>(), any::<String>(), 0..=shape.max_headers).boxed()
+    }
+}
+
+/// Absolute paths as the host sends them, percent-decoded: segments of unreserved
+/// characters, sub-delimiters, spaces and non-ASCII letters.
+pub fn path() -> impl Strategy<Value = String> {
+    vec("([A-Za-z0-9._~!$&'()*+,;=:@ -]|\\p{L}){1,12}", 0..6).prop_map(|segments| {
+        if segments.is_empty() {
+            "/".to_string()
+        } else {
+            format!("/{}", segments.join("/"))
+        }
+    })
+}
+
+/// Percent-encodes `path` as it appears in a request line, keeping `/` and the characters
+/// allowed in path segments.
+pub fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Query strings, empty or starting with `?`.
+pub fn query() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just(String::new()),
+        vec("[a-z_]{1,8}=([A-Za-z0-9._~-]|%[0-9A-F]{2}){0,12}", 1..4)
+            .prop_map(|pairs| format!("?{}", pairs.join("&"))),
+    ]
+}
+
+/// MCP JSON-RPC 2.0 request or response bodies.
+pub fn mcp_body() -> impl Strategy<Value = Vec<u8>> {
+    let id = prop_oneof![
+        any::<u32>().prop_map(|id| id.to_string()),
+        "[a-z0-9-]{1,12}".prop_map(|id| format!("\"{}\"", id)),
+    ];
+    let request = (select(MCP_METHODS), id.clone(), "[a-z_]{1,12}", "[ -~&&[^\"\\\\]]{0,32}")
+        .prop_map(|(method, id, name, argument)| {
+            format!(
+                r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":{{"name":"{}","arguments":{{"input":"{}"}}}}}}"#,
+                id, method, name, argument
+            )
+        });
+    let result = (id.clone(), "[ -~&&[^\"\\\\]]{0,64}").prop_map(|(id, text)| {
+        format!(
+            r#"{{"jsonrpc":"2.0","id":{},"result":{{"content":[{{"type":"text","text":"{}"}}]}}}}"#,
+            id, text
+        )
+    });
+    let error = (id, -32700..-32000i32).prop_map(|(id, code)| {
+        format!(
+            r#"{{"jsonrpc":"2.0","id":{},"error":{{"code":{},"message":"error"}}}}"#,
+            id, code
+        )
+    });
+    prop_oneof![request, result, error].prop_map(String::into_bytes)
+}
+
+/// Bodies according to `shape`: empty, arbitrary bytes, MCP messages or large.
+pub fn body(shape: &Shape) -> BoxedStrategy<Bytes> {
+    let mut bodies = vec![
+        (1, Just(Vec::new()).boxed()),
+        (3, vec(any::<u8>(), 0..=shape.max_body).boxed()),
+    ];
+    if shape.mcp_bodies {
+        bodies.push((3, mcp_body().boxed()));
+    }
+    if let Some(max) = shape.large_body {
+        // Fill with a repeated pattern; generating every byte would dominate test time.
+        let large = (LARGE_BODY_MIN..=max, any::<u8>())
+            .prop_map(|(len, byte)| vec![byte; len])
+            .boxed();
+        bodies.push((1, large));
+    }
+    proptest::strategy::Union::new_weighted(bodies)
+        .prop_map(Bytes::from)
+        .boxed()
+}
+
+/// HTTP requests shaped by `shape`. With valid HTTP, `path` is percent-decoded as the host
+/// sends it, and `url` and `request_uri` carry its percent-encoded form.
+pub fn http_request(shape: Shape) -> BoxedStrategy<HttpRequest> {
+    let headers = headers(&shape);
+    let body = body(&shape);
+    if !shape.valid_http {
+        return (
+            any::<String>(),
+            any::<String>(),
+            any::<String>(),
+            headers,
+            body,
+            any::<String>(),
+            any::<String>(),
+        )
+            .prop_map(
+                |(method, url, path, headers, body, remote_addr, request_uri)| HttpRequest {
+                    method,
+                    url,
+                    path,
+                    headers,
+                    body,
+                    remote_addr,
+                    request_uri,
+                },
+            )
+            .boxed();
+    }
+
+    let host = prop_oneof![
+        Just("localhost:8090".to_string()),
+        "[a-z]{1,10}\\.(example|test)(:[1-9][0-9]{1,3})?",
+    ];
+    let remote_addr = (any::<[u8; 4]>(), 1024..u16::MAX)
+        .prop_map(|(ip, port)| format!("{}.{}.{}.{}:{}", ip[0], ip[1], ip[2], ip[3], port));
+    (method(), host, path(), query(), headers, body, remote_addr)
+        .prop_map(
+            |(method, host, path, query, headers, body, remote_addr)| HttpRequest {
+                method,
+                url: format!("http://{}{}{}", host, encode_path(&path), query),
+                request_uri: format!("{}{}", encode_path(&path), query),
+                path,
+                headers,
+                body,
+                remote_addr,
+            },
+        )
+        .boxed()
+}
+
+/// HTTP status codes between 100 and 599, favouring common ones.
+pub fn status_code() -> impl Strategy<Value = i32> {
+    prop_oneof![
+        select(&[200, 201, 204, 301, 400, 401, 403, 404, 429, 500, 502, 503][..]),
+        100..600i32,
+    ]
+}
+
+/// HTTP responses shaped by `shape`, sometimes carrying a modified request.
+pub fn http_response(shape: Shape) -> BoxedStrategy<HttpResponse> {
+    let status_code = if shape.valid_http {
+        status_code().boxed()
+    } else {
+        any::<i32>().boxed()
+    };
+    (
+        status_code,
+        headers(&shape),
+        body(&shape),
+        any::<bool>(),
+        proptest::option::weighted(0.2, http_request(shape)),
+    )
+        .prop_map(
+            |(status_code, headers, body, r#continue, modified_request)| HttpResponse {
+                status_code,
+                headers,
+                body,
+                r#continue,
+                modified_request,
+            },
+        )
+        .boxed()
+}
+
+/// Flows defined by the protocol.
+pub fn flow() -> impl Strategy<Value = Flow> {
+    select(&[Flow::Request, Flow::Response][..])
+}
+
+/// Capabilities listing each flow at most once.
+pub fn capabilities() -> impl Strategy<Value = Capabilities> {
+    proptest::sample::subsequence(&[Flow::Request, Flow::Response][..], 0..=2).prop_map(|flows| {
+        Capabilities {
+            flows: flows.into_iter().map(|flow| flow as i32).collect(),
+        }
+    })
+}
+
+/// Plugin metadata with a non-empty name and a semantic version.
+pub fn metadata() -> impl Strategy<Value = Metadata> {
+    (
+        "[a-z][a-z0-9-]{0,23}",
+        (0..10u32, 0..20u32, 0..100u32),
+        "[ -~]{0,64}",
+        "([0-9a-f]{40})?",
+        "(20[0-9]{2}-[01][0-9]-[0-3][0-9]T[0-2][0-9]:[0-5][0-9]:[0-5][0-9]Z)?",
+    )
+        .prop_map(
+            |(name, (major, minor, patch), description, commit_hash, build_date)| Metadata {
+                name,
+                version: format!("{}.{}.{}", major, minor, patch),
+                description,
+                commit_hash,
+                build_date,
+            },
+        )
+}
+
+/// Telemetry configuration with a sample ratio between 0 and 1.
+pub fn telemetry_config() -> impl Strategy<Value = TelemetryConfig> {
+    (
+        "(http://[a-z]{1,10}:4317)?",
+        "[a-z][a-z0-9-]{0,15}",
+        select(&["", "development", "staging", "production"][..]),
+        0.0..=1.0f64,
+    )
+        .prop_map(
+            |(otlp_endpoint, service_name, environment, sample_ratio)| TelemetryConfig {
+                otlp_endpoint,
+                service_name,
+                environment: environment.to_string(),
+                sample_ratio,
+            },
+        )
+}
+
+/// Plugin configuration with dotted `custom_config` keys.
+pub fn plugin_config() -> impl Strategy<Value = PluginConfig> {
+    (
+        proptest::option::of(telemetry_config()),
+        hash_map("[a-z_]{1,12}(\\.[a-z_]{1,12})?", "[ -~]{0,32}", 0..8),
+    )
+        .prop_map(|(telemetry, custom_config)| PluginConfig {
+            telemetry,
+            custom_config,
+        })
+}
+
+impl Arbitrary for HttpRequest {
+    type Parameters = Shape;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(shape: Shape) -> Self::Strategy {
+        http_request(shape)
+    }
+}
+
+impl Arbitrary for HttpResponse {
+    type Parameters = Shape;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(shape: Shape) -> Self::Strategy {
+        http_response(shape)
+    }
+}
+
+impl Arbitrary for Capabilities {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        capabilities().boxed()
+    }
+}
+
+impl Arbitrary for Metadata {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        metadata().boxed()
+    }
+}
+
+impl Arbitrary for TelemetryConfig {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        telemetry_config().boxed()
+    }
+}
+
+impl Arbitrary for PluginConfig {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        plugin_config().boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes `%XX` escapes, as the host does before sending the path.
+    fn decode(encoded: &str) -> String {
+        let bytes = encoded.as_bytes();
+        let mut decoded = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'%' {
+                decoded.push(u8::from_str_radix(&encoded[i + 1..i + 3], 16).unwrap());
+                i += 3;
+            } else {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+        String::from_utf8(decoded).unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn header_names_are_unique_ignoring_case(headers in headers(&Shape::default())) {
+            let mut names: Vec<String> =
+                headers.keys().map(|name| name.to_ascii_lowercase()).collect();
+            names.sort();
+            names.dedup();
+            prop_assert_eq!(names.len(), headers.len());
+        }
+
+        #[test]
+        fn paths_are_decoded_and_uris_encoded(request in http_request(Shape::default())) {
+            let uri_path = request.request_uri.split('?').next().unwrap();
+            prop_assert_eq!(decode(uri_path), request.path.clone());
+            prop_assert!(!uri_path.contains(' '));
+            prop_assert!(request.url.ends_with(&request.request_uri));
+        }
+    }
+
+    #[test]
+    fn encodes_what_a_segment_cannot_hold() {
+        assert_eq!(encode_path("/a b/é"), "/a%20b/%C3%A9");
+        assert_eq!(encode_path("/tools:call/x@y"), "/tools:call/x@y");
+    }
+}
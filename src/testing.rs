@@ -0,0 +1,524 @@
+//! Conformance checks plugin authors can run from their own tests.
+//!
+//! [`Conformance`] drives a [`Plugin`] through the lifecycle the host uses (metadata,
+//! capabilities, configure, request and response handling, stop) and reports every rule of
+//! the plugin contract it violates. Call it from a `#[tokio::test]`:
+//!
+//! ```rust,no_run
+//! use mcpd_plugins_sdk::testing::Conformance;
+//! # struct MyPlugin;
+//! # #[tonic::async_trait]
+//! # impl mcpd_plugins_sdk::Plugin for MyPlugin {}
+//!
+//! # async fn plugin_conforms() {
+//! Conformance::new().run(&MyPlugin).await.assert_ok();
+//! # }
+//! ```
+
+use crate::plugin::Plugin;
+use crate::proto::{Flow, HttpRequest, HttpResponse, PluginConfig};
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::fmt;
+use tonic::{Request, Status};
+
+/// A rule of the plugin contract checked by [`Conformance`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Rule {
+    /// `GetMetadata` succeeds with a non-empty name.
+    MetadataName,
+
+    /// `GetMetadata` succeeds with a non-empty version.
+    MetadataVersion,
+
+    /// `GetCapabilities` succeeds and lists each flow at most once, since the flows are a set.
+    UniqueFlows,
+
+    /// `GetCapabilities` only lists flows defined by the protocol.
+    KnownFlows,
+
+    /// `Configure` accepts the configuration the suite was given.
+    Configure,
+
+    /// `HandleRequest` passes requests through unchanged unless the request flow is declared.
+    RequestFlowDeclared,
+
+    /// `HandleResponse` passes responses through unchanged unless the response flow is declared.
+    ResponseFlowDeclared,
+
+    /// A continuing `HandleResponse` keeps the status code and does not drop the body.
+    ResponsePreserved,
+
+    /// `Stop` succeeds when called more than once.
+    IdempotentStop,
+}
+
+impl Rule {
+    /// Returns the rule name used in reports.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rule::MetadataName => "metadata-name",
+            Rule::MetadataVersion => "metadata-version",
+            Rule::UniqueFlows => "unique-flows",
+            Rule::KnownFlows => "known-flows",
+            Rule::Configure => "configure",
+            Rule::RequestFlowDeclared => "request-flow-declared",
+            Rule::ResponseFlowDeclared => "response-flow-declared",
+            Rule::ResponsePreserved => "response-preserved",
+            Rule::IdempotentStop => "idempotent-stop",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A violated [`Rule`], with what the suite observed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// The violated rule.
+    pub rule: Rule,
+
+    /// What the suite observed.
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.rule, self.message)
+    }
+}
+
+/// Outcome of a [`Conformance`] run.
+#[derive(Debug, Clone, Default)]
+pub struct ConformanceReport {
+    /// Violations in the order they were found.
+    pub violations: Vec<Violation>,
+}
+
+impl ConformanceReport {
+    /// Returns whether the plugin satisfied every rule.
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Returns whether `rule` was violated.
+    pub fn violates(&self, rule: Rule) -> bool {
+        self.violations
+            .iter()
+            .any(|violation| violation.rule == rule)
+    }
+
+    /// Panics with the list of violations, if any.
+    #[track_caller]
+    pub fn assert_ok(&self) {
+        if !self.is_ok() {
+            panic!("{}", self);
+        }
+    }
+
+    fn violation(&mut self, rule: Rule, message: impl Into<String>) {
+        self.violations.push(Violation {
+            rule,
+            message: message.into(),
+        });
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return write!(f, "plugin conforms");
+        }
+        writeln!(f, "plugin violates {} rule(s):", self.violations.len())?;
+        for violation in &self.violations {
+            writeln!(f, "  {}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the plugin contract checks against a plugin.
+///
+/// The suite configures the plugin with an empty configuration unless one is given, then
+/// probes `HandleRequest` and `HandleResponse` with a few typical messages. Add probes that
+/// reach the plugin's interesting paths with [`request`](Self::request) and
+/// [`response`](Self::response).
+pub struct Conformance {
+    config: PluginConfig,
+    requests: Vec<HttpRequest>,
+    responses: Vec<HttpResponse>,
+}
+
+impl Default for Conformance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Conformance {
+    /// Creates a suite with the default probes.
+    pub fn new() -> Self {
+        Self {
+            config: PluginConfig::default(),
+            requests: default_requests(),
+            responses: default_responses(),
+        }
+    }
+
+    /// Sets the configuration passed to `Configure`.
+    pub fn config(mut self, config: PluginConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Adds a request to probe `HandleRequest` with.
+    pub fn request(mut self, request: HttpRequest) -> Self {
+        self.requests.push(request);
+        self
+    }
+
+    /// Adds a response to probe `HandleResponse` with.
+    pub fn response(mut self, response: HttpResponse) -> Self {
+        self.responses.push(response);
+        self
+    }
+
+    /// Runs every check against `plugin` and reports the violations.
+    pub async fn run<P: Plugin>(&self, plugin: &P) -> ConformanceReport {
+        let mut report = ConformanceReport::default();
+
+        match plugin.get_metadata(Request::new(())).await {
+            Ok(metadata) => {
+                let metadata = metadata.into_inner();
+                if metadata.name.trim().is_empty() {
+                    report.violation(Rule::MetadataName, "metadata name is empty");
+                }
+                if metadata.version.trim().is_empty() {
+                    report.violation(Rule::MetadataVersion, "metadata version is empty");
+                }
+            }
+            Err(status) => {
+                let message = format!("GetMetadata failed: {}", describe(&status));
+                report.violation(Rule::MetadataName, message.clone());
+                report.violation(Rule::MetadataVersion, message);
+            }
+        }
+
+        let flows = match plugin.get_capabilities(Request::new(())).await {
+            Ok(capabilities) => capabilities.into_inner().flows,
+            Err(status) => {
+                report.violation(
+                    Rule::UniqueFlows,
+                    format!("GetCapabilities failed: {}", describe(&status)),
+                );
+                Vec::new()
+            }
+        };
+        let mut seen = Vec::new();
+        for &flow in &flows {
+            match Flow::try_from(flow) {
+                Ok(known) if seen.contains(&known) => report.violation(
+                    Rule::UniqueFlows,
+                    format!("{} is listed more than once", known.as_str_name()),
+                ),
+                Ok(known) => seen.push(known),
+                Err(_) => report.violation(Rule::KnownFlows, format!("unknown flow {}", flow)),
+            }
+        }
+
+        if let Err(status) = plugin.configure(Request::new(self.config.clone())).await {
+            report.violation(
+                Rule::Configure,
+                format!("Configure failed: {}", describe(&status)),
+            );
+        }
+
+        self.check_requests(plugin, seen.contains(&Flow::Request), &mut report)
+            .await;
+        self.check_responses(plugin, seen.contains(&Flow::Response), &mut report)
+            .await;
+
+        for attempt in ["first", "second"] {
+            if let Err(status) = plugin.stop(Request::new(())).await {
+                report.violation(
+                    Rule::IdempotentStop,
+                    format!("{} Stop failed: {}", attempt, describe(&status)),
+                );
+            }
+        }
+
+        report
+    }
+
+    async fn check_requests<P: Plugin>(
+        &self,
+        plugin: &P,
+        declared: bool,
+        report: &mut ConformanceReport,
+    ) {
+        for request in &self.requests {
+            let result = plugin.handle_request(Request::new(request.clone())).await;
+            if declared {
+                continue;
+            }
+
+            let expected = PassThrough
+                .handle_request(Request::new(request.clone()))
+                .await
+                .map(|response| response.into_inner());
+            let actual = result.map(|response| response.into_inner());
+            if !same(&actual, &expected) {
+                report.violation(
+                    Rule::RequestFlowDeclared,
+                    format!(
+                        "{} {} was not passed through, but FLOW_REQUEST is not declared",
+                        request.method, request.path
+                    ),
+                );
+            }
+        }
+    }
+
+    async fn check_responses<P: Plugin>(
+        &self,
+        plugin: &P,
+        declared: bool,
+        report: &mut ConformanceReport,
+    ) {
+        for response in &self.responses {
+            let result = plugin
+                .handle_response(Request::new(response.clone()))
+                .await
+                .map(|output| output.into_inner());
+
+            if let Ok(output) = &result {
+                if output.r#continue && output.status_code != response.status_code {
+                    report.violation(
+                        Rule::ResponsePreserved,
+                        format!(
+                            "continuing response changed status {} to {}",
+                            response.status_code, output.status_code
+                        ),
+                    );
+                }
+                if output.r#continue && !response.body.is_empty() && output.body.is_empty() {
+                    report.violation(
+                        Rule::ResponsePreserved,
+                        format!(
+                            "continuing response dropped the body of a {} response",
+                            response.status_code
+                        ),
+                    );
+                }
+            }
+
+            if !declared {
+                let expected = PassThrough
+                    .handle_response(Request::new(response.clone()))
+                    .await
+                    .map(|output| output.into_inner());
+                if !same(&result, &expected) {
+                    report.violation(
+                        Rule::ResponseFlowDeclared,
+                        format!(
+                            "{} response was not passed through, but FLOW_RESPONSE is not declared",
+                            response.status_code
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Plugin with every default, whose handlers define pass-through behaviour.
+struct PassThrough;
+
+#[tonic::async_trait]
+impl Plugin for PassThrough {}
+
+fn same(
+    actual: &std::result::Result<HttpResponse, Status>,
+    expected: &std::result::Result<HttpResponse, Status>,
+) -> bool {
+    match (actual, expected) {
+        (Ok(actual), Ok(expected)) => actual == expected,
+        _ => false,
+    }
+}
+
+fn describe(status: &Status) -> String {
+    format!("{:?}: {}", status.code(), status.message())
+}
+
+fn default_requests() -> Vec<HttpRequest> {
+    vec![
+        HttpRequest {
+            method: "GET".to_string(),
+            url: "http://localhost:8090/api/v1/health".to_string(),
+            path: "/api/v1/health".to_string(),
+            headers: headers(&[("Accept", "application/json")]),
+            remote_addr: "127.0.0.1:54321".to_string(),
+            request_uri: "/api/v1/health".to_string(),
+            ..Default::default()
+        },
+        HttpRequest {
+            method: "POST".to_string(),
+            url: "http://localhost:8090/api/v1/servers/time/tools/get_current_time".to_string(),
+            path: "/api/v1/servers/time/tools/get_current_time".to_string(),
+            headers: headers(&[
+                ("Content-Type", "application/json"),
+                ("Authorization", "Bearer conformance"),
+            ]),
+            body: Bytes::from_static(br#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_current_time","arguments":{}}}"#),
+            remote_addr: "127.0.0.1:54322".to_string(),
+            request_uri: "/api/v1/servers/time/tools/get_current_time".to_string(),
+        },
+    ]
+}
+
+fn default_responses() -> Vec<HttpResponse> {
+    vec![
+        HttpResponse {
+            status_code: 200,
+            headers: headers(&[("Content-Type", "application/json")]),
+            body: Bytes::from_static(br#"{"jsonrpc":"2.0","id":1,"result":{"content":[]}}"#),
+            ..Default::default()
+        },
+        HttpResponse {
+            status_code: 500,
+            headers: headers(&[("Content-Type", "text/plain")]),
+            body: Bytes::from_static(b"Internal Server Error"),
+            ..Default::default()
+        },
+    ]
+}
+
+fn headers(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{Capabilities, Metadata};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tonic::Response;
+
+    /// Breaks every rule of the contract.
+    #[derive(Default)]
+    struct Broken {
+        stopped: AtomicBool,
+    }
+
+    #[tonic::async_trait]
+    impl Plugin for Broken {
+        async fn get_metadata(
+            &self,
+            _request: Request<()>,
+        ) -> std::result::Result<Response<Metadata>, Status> {
+            Ok(Response::new(Metadata::default()))
+        }
+
+        async fn get_capabilities(
+            &self,
+            _request: Request<()>,
+        ) -> std::result::Result<Response<Capabilities>, Status> {
+            Ok(Response::new(Capabilities {
+                flows: vec![Flow::Response as i32, Flow::Response as i32, 42],
+            }))
+        }
+
+        async fn configure(
+            &self,
+            _request: Request<PluginConfig>,
+        ) -> std::result::Result<Response<()>, Status> {
+            Err(Status::invalid_argument("no configuration accepted"))
+        }
+
+        async fn handle_request(
+            &self,
+            _request: Request<HttpRequest>,
+        ) -> std::result::Result<Response<HttpResponse>, Status> {
+            Ok(Response::new(HttpResponse {
+                r#continue: false,
+                status_code: 403,
+                ..Default::default()
+            }))
+        }
+
+        async fn handle_response(
+            &self,
+            _response: Request<HttpResponse>,
+        ) -> std::result::Result<Response<HttpResponse>, Status> {
+            Ok(Response::new(HttpResponse {
+                r#continue: true,
+                status_code: 204,
+                ..Default::default()
+            }))
+        }
+
+        async fn stop(&self, _request: Request<()>) -> std::result::Result<Response<()>, Status> {
+            if self.stopped.swap(true, Ordering::SeqCst) {
+                return Err(Status::failed_precondition("already stopped"));
+            }
+            Ok(Response::new(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn pass_through_plugin_conforms_except_for_metadata() {
+        let report = Conformance::new().run(&PassThrough).await;
+        let rules: Vec<Rule> = report.violations.iter().map(|v| v.rule).collect();
+        assert_eq!(rules, [Rule::MetadataName, Rule::MetadataVersion]);
+    }
+
+    #[tokio::test]
+    async fn reports_every_violated_rule() {
+        let report = Conformance::new().run(&Broken::default()).await;
+        for rule in [
+            Rule::MetadataName,
+            Rule::MetadataVersion,
+            Rule::UniqueFlows,
+            Rule::KnownFlows,
+            Rule::Configure,
+            Rule::RequestFlowDeclared,
+            Rule::ResponsePreserved,
+            Rule::IdempotentStop,
+        ] {
+            assert!(report.violates(rule), "{} not reported:\n{}", rule, report);
+        }
+        // The response flow is declared, so handling responses is allowed.
+        assert!(!report.violates(Rule::ResponseFlowDeclared), "{}", report);
+    }
+
+    #[tokio::test]
+    async fn undeclared_response_handling_is_reported() {
+        struct Rewrites;
+
+        #[tonic::async_trait]
+        impl Plugin for Rewrites {
+            async fn handle_response(
+                &self,
+                response: Request<HttpResponse>,
+            ) -> std::result::Result<Response<HttpResponse>, Status> {
+                let mut response = response.into_inner();
+                response.r#continue = true;
+                response.headers.insert("x-rewritten".into(), "yes".into());
+                Ok(Response::new(response))
+            }
+        }
+
+        let report = Conformance::new().run(&Rewrites).await;
+        assert!(report.violates(Rule::ResponseFlowDeclared), "{}", report);
+        assert!(!report.violates(Rule::ResponsePreserved), "{}", report);
+    }
+}
@@ -0,0 +1,179 @@
+use clap::ValueEnum;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::error::Elapsed;
+use tonic::metadata::MetadataMap;
+
+/// Name of the gRPC header carrying the caller's deadline.
+const GRPC_TIMEOUT_HEADER: &str = "grpc-timeout";
+
+/// Bounds of the share of the caller's deadline kept back from the plugin.
+///
+/// tonic's server enforces the `grpc-timeout` deadline around the whole call, starting before
+/// the request is decoded, and answers `Cancelled` when it expires. The plugin's deadline
+/// ends a tenth of the caller's deadline earlier, within these bounds, so that the timeout
+/// policy is applied and reported instead.
+const DEADLINE_MARGIN_MIN: Duration = Duration::from_millis(5);
+const DEADLINE_MARGIN_MAX: Duration = Duration::from_millis(100);
+
+/// What the plugin returns when a request or response handler exceeds its deadline.
+///
+/// The policy only applies to `HandleRequest` and `HandleResponse`. Every other RPC
+/// always fails closed, since there is no meaningful pass-through result for it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TimeoutPolicy {
+    /// Return a `DeadlineExceeded` status to the host.
+    #[default]
+    FailClosed,
+
+    /// Return `HttpResponse { continue: true }` so the host carries on with the
+    /// request or response unchanged.
+    FailOpen,
+}
+
+impl TimeoutPolicy {
+    /// Returns the policy name as used on the command line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeoutPolicy::FailClosed => "fail-closed",
+            TimeoutPolicy::FailOpen => "fail-open",
+        }
+    }
+}
+
+/// Returns the deadline requested by the caller via the `grpc-timeout` header, if any.
+///
+/// Malformed values are ignored, following the gRPC over HTTP/2 specification.
+pub(crate) fn parse_grpc_timeout(metadata: &MetadataMap) -> Option<Duration> {
+    let value = metadata.get(GRPC_TIMEOUT_HEADER)?.to_str().ok()?;
+    if value.len() < 2 {
+        return None;
+    }
+
+    let (amount, unit) = value.split_at(value.len() - 1);
+
+    // The specification limits the value to at most 8 digits, which also rules out overflow.
+    if amount.len() > 8 || !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let amount: u64 = amount.parse().ok()?;
+
+    match unit {
+        "H" => Some(Duration::from_secs(amount * 60 * 60)),
+        "M" => Some(Duration::from_secs(amount * 60)),
+        "S" => Some(Duration::from_secs(amount)),
+        "m" => Some(Duration::from_millis(amount)),
+        "u" => Some(Duration::from_micros(amount)),
+        "n" => Some(Duration::from_nanos(amount)),
+        _ => None,
+    }
+}
+
+/// Returns the shorter of the configured timeout and the caller's deadline, less the margin
+/// kept back for tonic (see [`DEADLINE_MARGIN_MIN`]).
+pub(crate) fn effective_timeout(
+    configured: Option<Duration>,
+    metadata: &MetadataMap,
+) -> Option<Duration> {
+    let requested = parse_grpc_timeout(metadata).map(|requested| {
+        let margin = (requested / 10).clamp(DEADLINE_MARGIN_MIN, DEADLINE_MARGIN_MAX);
+        requested.saturating_sub(margin)
+    });
+    match (configured, requested) {
+        (Some(configured), Some(requested)) => Some(configured.min(requested)),
+        (configured, requested) => configured.or(requested),
+    }
+}
+
+/// Awaits `future`, giving up once `timeout` has elapsed (if one is set).
+pub(crate) async fn within<F: Future>(
+    timeout: Option<Duration>,
+    future: F,
+) -> std::result::Result<F::Output, Elapsed> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, future).await,
+        None => Ok(future.await),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(grpc_timeout: &str) -> MetadataMap {
+        let mut metadata = MetadataMap::new();
+        metadata.insert(GRPC_TIMEOUT_HEADER, grpc_timeout.parse().unwrap());
+        metadata
+    }
+
+    fn parse(grpc_timeout: &str) -> Option<Duration> {
+        parse_grpc_timeout(&metadata(grpc_timeout))
+    }
+
+    #[test]
+    fn parses_every_unit() {
+        assert_eq!(parse("2H"), Some(Duration::from_secs(2 * 60 * 60)));
+        assert_eq!(parse("3M"), Some(Duration::from_secs(3 * 60)));
+        assert_eq!(parse("4S"), Some(Duration::from_secs(4)));
+        assert_eq!(parse("500m"), Some(Duration::from_millis(500)));
+        assert_eq!(parse("600u"), Some(Duration::from_micros(600)));
+        assert_eq!(parse("700n"), Some(Duration::from_nanos(700)));
+    }
+
+    #[test]
+    fn accepts_eight_digits_without_overflow() {
+        assert_eq!(
+            parse("99999999H"),
+            Some(Duration::from_secs(99_999_999 * 60 * 60))
+        );
+        assert_eq!(parse("00000001m"), Some(Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn ignores_malformed_values() {
+        for value in [
+            "",
+            "m",
+            "5",
+            "5x",
+            "5 m",
+            "-5m",
+            "+5m",
+            "1.5S",
+            "999999999m",
+            "5mm",
+            "abcm",
+        ] {
+            assert_eq!(parse(value), None, "{:?}", value);
+        }
+        assert_eq!(parse_grpc_timeout(&MetadataMap::new()), None);
+    }
+
+    #[test]
+    fn keeps_a_margin_of_the_callers_deadline() {
+        // A tenth of the deadline, at least 5ms and at most 100ms.
+        let effective = |value| effective_timeout(None, &metadata(value));
+        assert_eq!(effective("1S"), Some(Duration::from_millis(900)));
+        assert_eq!(effective("20m"), Some(Duration::from_millis(15)));
+        assert_eq!(effective("3m"), Some(Duration::ZERO));
+        assert_eq!(effective("10S"), Some(Duration::from_millis(9_900)));
+    }
+
+    #[test]
+    fn uses_the_shorter_deadline() {
+        let configured = Some(Duration::from_millis(200));
+        assert_eq!(
+            effective_timeout(configured, &metadata("1S")),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(
+            effective_timeout(configured, &metadata("100m")),
+            Some(Duration::from_millis(90))
+        );
+        assert_eq!(
+            effective_timeout(configured, &MetadataMap::new()),
+            configured
+        );
+        assert_eq!(effective_timeout(None, &MetadataMap::new()), None);
+    }
+}
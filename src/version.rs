@@ -0,0 +1,173 @@
+use crate::layer::{BoxPluginService, NamedPluginService};
+use crate::{PluginError, Result};
+use clap::ValueEnum;
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::transport::server::Router;
+use tracing::warn;
+
+/// Header in which the host lists the protocol versions it speaks, and in which
+/// `GetMetadata` responses list the versions the plugin serves (comma-separated).
+pub const PROTOCOL_VERSIONS_HEADER: &str = "mcpd-plugin-protocol-versions";
+
+/// Header in which `GetMetadata` responses name the newest version both sides support,
+/// when the host sent [`PROTOCOL_VERSIONS_HEADER`].
+pub const PROTOCOL_VERSION_HEADER: &str = "mcpd-plugin-protocol-version";
+
+/// A version of the plugin gRPC protocol (`mozilla.mcpd.plugins.<version>`).
+///
+/// Each version is served only when its `proto-<version>` feature is enabled, see
+/// [`ALL`](Self::ALL). The [`Plugin`](crate::Plugin) trait is written against v1; other
+/// versions are served by adapters that translate their messages to and from v1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+#[non_exhaustive]
+pub enum ProtocolVersion {
+    /// `mozilla.mcpd.plugins.v1`.
+    V1,
+}
+
+impl ProtocolVersion {
+    /// Versions compiled into this build by their features, oldest first.
+    pub const ALL: &'static [ProtocolVersion] = &[
+        #[cfg(feature = "proto-v1")]
+        ProtocolVersion::V1,
+    ];
+
+    /// Returns the version name as used in package names, e.g. `v1`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProtocolVersion::V1 => "v1",
+        }
+    }
+
+    /// Checks that this version is compiled into this build.
+    pub(crate) fn check_compiled(&self) -> Result<()> {
+        if Self::ALL.contains(self) {
+            Ok(())
+        } else {
+            Err(PluginError::Configuration(format!(
+                "protocol {} requires the `proto-{}` feature of mcpd-plugins-sdk",
+                self, self
+            )))
+        }
+    }
+
+    /// Adds the plugin service for this version to `router`.
+    ///
+    /// `service` is the v1 plugin service. v1 is served as is; each later version wraps it
+    /// in an adapter that translates that version's messages to and from v1.
+    pub(crate) fn add_service(&self, router: Router, service: BoxPluginService) -> Result<Router> {
+        self.check_compiled()?;
+        match self {
+            ProtocolVersion::V1 => Ok(router.add_service(NamedPluginService(service))),
+        }
+    }
+
+    /// Returns the encoded file descriptor set of this version's proto, served by reflection.
+    #[cfg(feature = "reflection")]
+    pub(crate) fn file_descriptor_set(&self) -> &'static [u8] {
+        match self {
+            ProtocolVersion::V1 => crate::proto::v1::FILE_DESCRIPTOR_SET,
+        }
+    }
+}
+
+impl std::fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Joins `versions` into a header value such as `v1,v2`.
+pub(crate) fn join(versions: &[ProtocolVersion]) -> String {
+    versions
+        .iter()
+        .map(ProtocolVersion::as_str)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Returns the versions the host offered in [`PROTOCOL_VERSIONS_HEADER`], if it sent any.
+///
+/// Versions unknown to this build are skipped.
+pub(crate) fn offered(metadata: &MetadataMap) -> Option<Vec<ProtocolVersion>> {
+    let value = metadata.get(PROTOCOL_VERSIONS_HEADER)?.to_str().ok()?;
+    Some(
+        value
+            .split(',')
+            .filter_map(|version| ProtocolVersion::from_str(version.trim(), true).ok())
+            .collect(),
+    )
+}
+
+/// Reports the `served` versions, and the one negotiated with the host's `offered`
+/// versions, in the response metadata of `GetMetadata`.
+pub(crate) fn report(
+    metadata: &mut MetadataMap,
+    served: &[ProtocolVersion],
+    offered: Option<&[ProtocolVersion]>,
+) {
+    if let Ok(value) = MetadataValue::try_from(join(served)) {
+        metadata.insert(PROTOCOL_VERSIONS_HEADER, value);
+    }
+
+    let Some(offered) = offered else {
+        return;
+    };
+    match served.iter().filter(|v| offered.contains(v)).max() {
+        Some(version) => {
+            metadata.insert(
+                PROTOCOL_VERSION_HEADER,
+                MetadataValue::from_static(version.as_str()),
+            );
+        }
+        None => warn!(
+            "Host offered no protocol version this plugin serves (serving {})",
+            join(served)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn negotiate(served: &[ProtocolVersion], offered: &str) -> MetadataMap {
+        let mut request = MetadataMap::new();
+        request.insert(PROTOCOL_VERSIONS_HEADER, offered.parse().unwrap());
+        let offered = self::offered(&request);
+
+        let mut response = MetadataMap::new();
+        report(&mut response, served, offered.as_deref());
+        response
+    }
+
+    #[test]
+    fn compiled_versions_follow_features() {
+        assert_eq!(
+            ProtocolVersion::ALL.contains(&ProtocolVersion::V1),
+            cfg!(feature = "proto-v1")
+        );
+        for version in ProtocolVersion::ALL {
+            version.check_compiled().unwrap();
+        }
+    }
+
+    #[test]
+    fn skips_unknown_offered_versions() {
+        let mut metadata = MetadataMap::new();
+        metadata.insert(PROTOCOL_VERSIONS_HEADER, "v9, V1,".parse().unwrap());
+        assert_eq!(offered(&metadata), Some(vec![ProtocolVersion::V1]));
+        assert_eq!(offered(&MetadataMap::new()), None);
+    }
+
+    #[test]
+    fn reports_served_and_negotiated_versions() {
+        let response = negotiate(&[ProtocolVersion::V1], "v1,v9");
+        assert_eq!(response.get(PROTOCOL_VERSIONS_HEADER).unwrap(), "v1");
+        assert_eq!(response.get(PROTOCOL_VERSION_HEADER).unwrap(), "v1");
+
+        let response = negotiate(&[ProtocolVersion::V1], "v9");
+        assert_eq!(response.get(PROTOCOL_VERSIONS_HEADER).unwrap(), "v1");
+        assert!(response.get(PROTOCOL_VERSION_HEADER).is_none());
+    }
+}
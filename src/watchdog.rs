@@ -0,0 +1,138 @@
+/// How often the host process is checked for liveness.
+#[cfg(unix)]
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// The parent pid as first seen by the SDK, see [`capture_parent()`].
+#[cfg(unix)]
+static PARENT: std::sync::OnceLock<i32> = std::sync::OnceLock::new();
+
+/// Remembers the current parent pid as the host to watch with [`HostWatch::Parent`].
+///
+/// Called when the server is configured, at process start in practice, so a host that
+/// dies while the plugin is still starting up is noticed: by then the plugin has been
+/// re-parented and the parent pid no longer matches.
+pub(crate) fn capture_parent() {
+    #[cfg(unix)]
+    PARENT.get_or_init(parent_pid);
+}
+
+#[cfg(unix)]
+fn parent_pid() -> i32 {
+    // SAFETY: getppid has no preconditions.
+    unsafe { libc::getppid() }
+}
+
+/// The process whose exit shuts the plugin down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(unix), allow(dead_code))]
+pub(crate) enum HostWatch {
+    /// The process that started the plugin.
+    Parent,
+
+    /// A specific process id, for hosts that start plugins through a launcher.
+    Pid(i32),
+}
+
+/// Resolves once the watched host process has exited.
+///
+/// Watching the parent also asks Linux to deliver SIGTERM when it dies, so shutdown
+/// starts immediately rather than at the next poll. Callers must be listening for
+/// SIGTERM before awaiting this.
+#[cfg(unix)]
+pub(crate) async fn host_exited(host: HostWatch) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    match host {
+        HostWatch::Parent => {
+            let parent = *PARENT.get_or_init(parent_pid);
+
+            // The signal is only delivered for a parent dying after this call; an earlier
+            // death is caught by the first parent pid check below.
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            {
+                // SAFETY: PR_SET_PDEATHSIG takes a signal number and has no other effects.
+                if unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM) } != 0 {
+                    tracing::warn!(
+                        "Failed to set parent death signal, falling back to polling: {}",
+                        std::io::Error::last_os_error()
+                    );
+                }
+            }
+
+            parent_exited(parent, interval).await;
+        }
+        HostWatch::Pid(pid) => {
+            while is_alive(pid) {
+                interval.tick().await;
+            }
+        }
+    }
+}
+
+/// Resolves once the plugin's parent is no longer `parent`.
+///
+/// Once the parent dies the plugin is re-parented, so its parent pid changes.
+#[cfg(unix)]
+async fn parent_exited(parent: i32, mut interval: tokio::time::Interval) {
+    while parent_pid() == parent {
+        interval.tick().await;
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) async fn host_exited(_host: HostWatch) {
+    std::future::pending::<()>().await
+}
+
+/// Returns whether a process with id `pid` exists.
+#[cfg(unix)]
+fn is_alive(pid: i32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists and may be signalled.
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+
+    // EPERM means the process exists but belongs to another user.
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn own_process_is_alive() {
+        assert!(is_alive(std::process::id() as i32));
+    }
+
+    #[tokio::test]
+    async fn exited_host_is_noticed() {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id() as i32;
+        child.wait().unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), host_exited(HostWatch::Pid(pid)))
+            .await
+            .expect("exited host not noticed");
+    }
+
+    #[tokio::test]
+    async fn parent_that_died_before_watching_is_noticed() {
+        // A parent pid captured earlier that differs from the current one, as after the
+        // plugin has been re-parented.
+        let interval = tokio::time::interval(POLL_INTERVAL);
+        tokio::time::timeout(Duration::from_millis(100), parent_exited(-1, interval))
+            .await
+            .expect("re-parenting not noticed");
+    }
+
+    #[tokio::test]
+    async fn live_parent_is_watched() {
+        let interval = tokio::time::interval(Duration::from_millis(10));
+        let watch = parent_exited(parent_pid(), interval);
+        assert!(tokio::time::timeout(Duration::from_millis(100), watch)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,207 @@
+//! Bodyless flows and the omission of unchanged bodies, through the adapter.
+
+use bytes::Bytes;
+use mcpd_plugins_sdk::proto::plugin_server::Plugin as PluginService;
+use mcpd_plugins_sdk::{
+    Capabilities, Flow, HttpRequest, HttpResponse, Plugin, PluginAdapter, PluginChain,
+    BODYLESS_FLOWS_HEADER, BODY_UNCHANGED_HEADER, FLOW_REQUEST, OMIT_UNCHANGED_BODY_HEADER,
+};
+use tonic::{Request, Response, Status};
+
+/// Adds a header to requests and leaves bodies alone, without needing request bodies.
+struct HeaderOnly;
+
+#[tonic::async_trait]
+impl Plugin for HeaderOnly {
+    async fn get_capabilities(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<Capabilities>, Status> {
+        Ok(Response::new(Capabilities {
+            flows: vec![FLOW_REQUEST as i32],
+        }))
+    }
+
+    fn needs_body(&self, flow: Flow) -> bool {
+        flow != Flow::Request
+    }
+
+    async fn handle_request(
+        &self,
+        request: Request<HttpRequest>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        let mut request = request.into_inner();
+        request
+            .headers
+            .insert("x-seen".to_string(), "yes".to_string());
+        Ok(Response::new(HttpResponse {
+            r#continue: true,
+            modified_request: Some(request),
+            ..Default::default()
+        }))
+    }
+}
+
+/// Rebuilds bodies with the same content, or rejects requests with their body echoed.
+struct Rebuilds {
+    reject: bool,
+}
+
+#[tonic::async_trait]
+impl Plugin for Rebuilds {
+    async fn handle_request(
+        &self,
+        request: Request<HttpRequest>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        let mut request = request.into_inner();
+        let body = Bytes::copy_from_slice(&request.body);
+        if self.reject {
+            return Ok(Response::new(HttpResponse {
+                r#continue: false,
+                status_code: 400,
+                body: request.body,
+                ..Default::default()
+            }));
+        }
+        request.body = body;
+        Ok(Response::new(HttpResponse {
+            r#continue: true,
+            modified_request: Some(request),
+            ..Default::default()
+        }))
+    }
+
+    async fn handle_response(
+        &self,
+        response: Request<HttpResponse>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        let mut response = response.into_inner();
+        response.body = Bytes::copy_from_slice(&response.body);
+        response.r#continue = true;
+        Ok(Response::new(response))
+    }
+}
+
+fn request(omit: bool) -> Request<HttpRequest> {
+    let mut request = Request::new(HttpRequest {
+        method: "POST".to_string(),
+        path: "/tools".to_string(),
+        body: Bytes::from_static(b"{\"jsonrpc\":\"2.0\"}"),
+        ..Default::default()
+    });
+    if omit {
+        request
+            .metadata_mut()
+            .insert(OMIT_UNCHANGED_BODY_HEADER, "true".parse().unwrap());
+    }
+    request
+}
+
+fn response(omit: bool) -> Request<HttpResponse> {
+    let mut response = Request::new(HttpResponse {
+        r#continue: true,
+        status_code: 200,
+        body: Bytes::from_static(b"{\"result\":{}}"),
+        ..Default::default()
+    });
+    if omit {
+        response
+            .metadata_mut()
+            .insert(OMIT_UNCHANGED_BODY_HEADER, "true".parse().unwrap());
+    }
+    response
+}
+
+fn marked(response: &Response<HttpResponse>) -> bool {
+    response.metadata().get(BODY_UNCHANGED_HEADER).is_some()
+}
+
+fn request_body(response: &Response<HttpResponse>) -> &[u8] {
+    &response.get_ref().modified_request.as_ref().unwrap().body
+}
+
+#[tokio::test]
+async fn reports_bodyless_flows() {
+    let adapter = PluginAdapter::new(HeaderOnly);
+    let capabilities = adapter.get_capabilities(Request::new(())).await.unwrap();
+    assert_eq!(
+        capabilities.metadata().get(BODYLESS_FLOWS_HEADER).unwrap(),
+        "FLOW_REQUEST"
+    );
+
+    let adapter = PluginAdapter::new(Rebuilds { reject: false });
+    let capabilities = adapter.get_capabilities(Request::new(())).await.unwrap();
+    assert!(capabilities.metadata().get(BODYLESS_FLOWS_HEADER).is_none());
+}
+
+#[tokio::test]
+async fn omits_an_untouched_request_body_when_asked() {
+    let adapter = PluginAdapter::new(HeaderOnly);
+
+    let result = adapter.handle_request(request(true)).await.unwrap();
+    assert!(marked(&result));
+    assert!(request_body(&result).is_empty());
+    let modified = result.get_ref().modified_request.as_ref().unwrap();
+    assert_eq!(modified.headers["x-seen"], "yes");
+
+    let result = adapter.handle_request(request(false)).await.unwrap();
+    assert!(!marked(&result));
+    assert_eq!(request_body(&result), b"{\"jsonrpc\":\"2.0\"}");
+}
+
+#[tokio::test]
+async fn omits_an_untouched_response_body_when_asked() {
+    // The default handler passes the response through.
+    let adapter = PluginAdapter::new(HeaderOnly);
+
+    let result = adapter.handle_response(response(true)).await.unwrap();
+    assert!(marked(&result));
+    assert!(result.get_ref().body.is_empty());
+    assert_eq!(result.get_ref().status_code, 200);
+
+    let result = adapter.handle_response(response(false)).await.unwrap();
+    assert!(!marked(&result));
+    assert_eq!(result.get_ref().body, b"{\"result\":{}}"[..]);
+}
+
+#[tokio::test]
+async fn sends_rebuilt_bodies_in_full() {
+    let adapter = PluginAdapter::new(Rebuilds { reject: false });
+
+    let result = adapter.handle_request(request(true)).await.unwrap();
+    assert!(!marked(&result));
+    assert_eq!(request_body(&result), b"{\"jsonrpc\":\"2.0\"}");
+
+    let result = adapter.handle_response(response(true)).await.unwrap();
+    assert!(!marked(&result));
+    assert_eq!(result.get_ref().body, b"{\"result\":{}}"[..]);
+}
+
+#[tokio::test]
+async fn keeps_the_body_of_a_rejection() {
+    let adapter = PluginAdapter::new(Rebuilds { reject: true });
+
+    let result = adapter.handle_request(request(true)).await.unwrap();
+    assert!(!marked(&result));
+    assert!(!result.get_ref().r#continue);
+    assert_eq!(result.get_ref().body, b"{\"jsonrpc\":\"2.0\"}"[..]);
+}
+
+#[tokio::test]
+async fn chains_keep_untouched_bodies_shared() {
+    let chain = PluginChain::new()
+        .add("first", HeaderOnly)
+        .add("second", HeaderOnly);
+    let adapter = PluginAdapter::new(chain);
+
+    // No plugin in the chain handles responses, so their bodies are not needed either.
+    let capabilities = adapter.get_capabilities(Request::new(())).await.unwrap();
+    assert_eq!(
+        capabilities.metadata().get(BODYLESS_FLOWS_HEADER).unwrap(),
+        "FLOW_REQUEST,FLOW_RESPONSE"
+    );
+
+    let result = adapter.handle_request(request(true)).await.unwrap();
+    assert!(marked(&result));
+    assert!(request_body(&result).is_empty());
+}
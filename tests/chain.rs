@@ -0,0 +1,312 @@
+//! Behaviour of a `PluginChain` documented on the type.
+
+use mcpd_plugins_sdk::{
+    Capabilities, HttpRequest, HttpResponse, Metadata, Plugin, PluginChain, PluginConfig,
+    FLOW_REQUEST, FLOW_RESPONSE,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tonic::{Code, Request, Response, Status};
+
+/// Returns `headers` with `continue`, or with `status_code` if that is not zero.
+struct Answer {
+    status_code: i32,
+    headers: &'static [(&'static str, &'static str)],
+}
+
+#[tonic::async_trait]
+impl Plugin for Answer {
+    async fn get_capabilities(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<Capabilities>, Status> {
+        Ok(Response::new(Capabilities {
+            flows: vec![FLOW_REQUEST as i32],
+        }))
+    }
+
+    async fn handle_request(
+        &self,
+        _request: Request<HttpRequest>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        Ok(Response::new(HttpResponse {
+            r#continue: self.status_code == 0,
+            status_code: self.status_code,
+            headers: self
+                .headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            ..Default::default()
+        }))
+    }
+}
+
+#[tokio::test]
+async fn rejection_keeps_headers_of_earlier_plugins() {
+    let chain = PluginChain::new()
+        .add(
+            "cors",
+            Answer {
+                status_code: 0,
+                headers: &[("access-control-allow-origin", "*"), ("x-shared", "cors")],
+            },
+        )
+        .add(
+            "auth",
+            Answer {
+                status_code: 401,
+                headers: &[("www-authenticate", "Bearer"), ("x-shared", "auth")],
+            },
+        )
+        .add(
+            "never",
+            Answer {
+                status_code: 0,
+                headers: &[("x-never", "reached")],
+            },
+        );
+
+    let response = chain
+        .handle_request(Request::new(HttpRequest::default()))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!response.r#continue);
+    assert_eq!(response.status_code, 401);
+    assert_eq!(
+        response.headers,
+        [
+            ("access-control-allow-origin", "*"),
+            ("www-authenticate", "Bearer"),
+            ("x-shared", "auth"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .into()
+    );
+}
+
+/// What a `Probe` has been called with.
+#[derive(Default)]
+struct Seen {
+    config: Option<HashMap<String, String>>,
+    requests: Vec<HttpRequest>,
+    stops: usize,
+}
+
+/// Plugin whose request handling is enabled by its `flows` config key (`request`), which
+/// tags requests with its name and records what it sees.
+struct Probe {
+    name: &'static str,
+    fail_stop: bool,
+    seen: Arc<Mutex<Seen>>,
+}
+
+impl Probe {
+    fn new(name: &'static str) -> (Self, Arc<Mutex<Seen>>) {
+        let seen = Arc::new(Mutex::new(Seen::default()));
+        let probe = Self {
+            name,
+            fail_stop: false,
+            seen: seen.clone(),
+        };
+        (probe, seen)
+    }
+}
+
+#[tonic::async_trait]
+impl Plugin for Probe {
+    async fn get_metadata(&self, _request: Request<()>) -> Result<Response<Metadata>, Status> {
+        Ok(Response::new(Metadata {
+            name: self.name.to_string(),
+            version: "1.0.0".to_string(),
+            ..Default::default()
+        }))
+    }
+
+    async fn get_capabilities(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<Capabilities>, Status> {
+        let seen = self.seen.lock().unwrap();
+        let handles_requests = seen
+            .config
+            .as_ref()
+            .and_then(|config| config.get("flows"))
+            .is_some_and(|flows| flows == "request");
+        let mut flows = vec![FLOW_RESPONSE as i32];
+        if handles_requests {
+            flows.push(FLOW_REQUEST as i32);
+        }
+        Ok(Response::new(Capabilities { flows }))
+    }
+
+    async fn configure(&self, request: Request<PluginConfig>) -> Result<Response<()>, Status> {
+        self.seen.lock().unwrap().config = Some(request.into_inner().custom_config);
+        Ok(Response::new(()))
+    }
+
+    async fn stop(&self, _request: Request<()>) -> Result<Response<()>, Status> {
+        self.seen.lock().unwrap().stops += 1;
+        if self.fail_stop {
+            return Err(Status::internal("stop failed"));
+        }
+        Ok(Response::new(()))
+    }
+
+    async fn handle_request(
+        &self,
+        request: Request<HttpRequest>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        let mut request = request.into_inner();
+        self.seen.lock().unwrap().requests.push(request.clone());
+        request
+            .headers
+            .insert(format!("x-{}", self.name), "seen".to_string());
+        Ok(Response::new(HttpResponse {
+            r#continue: true,
+            modified_request: Some(request),
+            ..Default::default()
+        }))
+    }
+}
+
+fn config(pairs: &[(&str, &str)]) -> PluginConfig {
+    PluginConfig {
+        custom_config: pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn configure_passes_each_plugin_its_namespace() {
+    let (auth, auth_seen) = Probe::new("auth");
+    let (limit, limit_seen) = Probe::new("limit");
+    let chain = PluginChain::new().add("auth", auth).add("limit", limit);
+
+    chain
+        .configure(Request::new(config(&[
+            ("auth.valid_tokens", "a,b"),
+            ("limit.max_requests", "10"),
+            ("authx.other", "no"),
+            ("valid_tokens", "no"),
+        ])))
+        .await
+        .unwrap();
+
+    let expected = |key: &str, value: &str| Some([(key.to_string(), value.to_string())].into());
+    assert_eq!(
+        auth_seen.lock().unwrap().config,
+        expected("valid_tokens", "a,b")
+    );
+    assert_eq!(
+        limit_seen.lock().unwrap().config,
+        expected("max_requests", "10")
+    );
+}
+
+#[tokio::test]
+async fn threads_modified_requests_through_the_chain() {
+    let (first, _) = Probe::new("first");
+    let (second, second_seen) = Probe::new("second");
+    let chain = PluginChain::new().add("first", first).add("second", second);
+    chain
+        .configure(Request::new(config(&[
+            ("first.flows", "request"),
+            ("second.flows", "request"),
+        ])))
+        .await
+        .unwrap();
+
+    let response = chain
+        .handle_request(Request::new(HttpRequest {
+            path: "/tools".to_string(),
+            ..Default::default()
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let seen = &second_seen.lock().unwrap().requests;
+    assert_eq!(seen.len(), 1);
+    assert_eq!(seen[0].headers["x-first"], "seen");
+    let modified = response.modified_request.unwrap();
+    assert_eq!(modified.path, "/tools");
+    assert_eq!(modified.headers.len(), 2);
+}
+
+#[tokio::test]
+async fn asks_for_flows_again_after_configure() {
+    let (probe, seen) = Probe::new("auth");
+    let chain = PluginChain::new().add("auth", probe);
+
+    // Flows are cached before configuration, without the request flow.
+    let capabilities = chain.get_capabilities(Request::new(())).await.unwrap();
+    assert_eq!(capabilities.into_inner().flows, [FLOW_RESPONSE as i32]);
+    chain
+        .handle_request(Request::new(HttpRequest::default()))
+        .await
+        .unwrap();
+    assert!(seen.lock().unwrap().requests.is_empty());
+
+    chain
+        .configure(Request::new(config(&[("auth.flows", "request")])))
+        .await
+        .unwrap();
+    chain
+        .handle_request(Request::new(HttpRequest::default()))
+        .await
+        .unwrap();
+    assert_eq!(seen.lock().unwrap().requests.len(), 1);
+}
+
+#[tokio::test]
+async fn stop_reaches_every_plugin_after_an_error() {
+    let (mut first, first_seen) = Probe::new("first");
+    first.fail_stop = true;
+    let (second, second_seen) = Probe::new("second");
+    let chain = PluginChain::new().add("first", first).add("second", second);
+
+    let status = chain.stop(Request::new(())).await.unwrap_err();
+    assert_eq!(status.code(), Code::Internal);
+    assert_eq!(status.message(), "first: stop failed");
+    assert_eq!(first_seen.lock().unwrap().stops, 1);
+    assert_eq!(second_seen.lock().unwrap().stops, 1);
+}
+
+#[tokio::test]
+async fn merges_metadata_and_capabilities() {
+    let (auth, _) = Probe::new("auth");
+    let (limit, _) = Probe::new("limit");
+    let chain = PluginChain::new()
+        .add("auth", auth)
+        .add("limit", limit)
+        .add(
+            "cors",
+            Answer {
+                status_code: 0,
+                headers: &[],
+            },
+        );
+    chain
+        .configure(Request::new(config(&[("limit.flows", "request")])))
+        .await
+        .unwrap();
+
+    let metadata = chain
+        .get_metadata(Request::new(()))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(metadata.name, "auth+limit");
+    assert_eq!(metadata.version, "1.0.0+1.0.0");
+
+    let capabilities = chain.get_capabilities(Request::new(())).await.unwrap();
+    assert_eq!(
+        capabilities.into_inner().flows,
+        [FLOW_RESPONSE as i32, FLOW_REQUEST as i32]
+    );
+}
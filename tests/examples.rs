@@ -0,0 +1,79 @@
+//! Runs the conformance suite against the example plugins.
+
+#[path = "../examples/simple_plugin/main.rs"]
+#[allow(dead_code)]
+mod simple_plugin;
+
+#[path = "../examples/auth_plugin/main.rs"]
+#[allow(dead_code)]
+mod auth_plugin;
+
+#[path = "../examples/rate_limit_plugin/main.rs"]
+#[allow(dead_code)]
+mod rate_limit_plugin;
+
+use mcpd_plugins_sdk::testing::Conformance;
+use mcpd_plugins_sdk::{HttpRequest, PluginConfig};
+
+fn request(path: &str, headers: &[(&str, &str)]) -> HttpRequest {
+    HttpRequest {
+        method: "GET".to_string(),
+        url: format!("http://localhost{}", path),
+        path: path.to_string(),
+        headers: headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+        remote_addr: "192.0.2.1:4000".to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn simple_plugin_conforms() {
+    Conformance::new()
+        .run(&simple_plugin::SimplePlugin)
+        .await
+        .assert_ok();
+}
+
+#[tokio::test]
+async fn auth_plugin_conforms() {
+    let config = PluginConfig {
+        custom_config: [
+            ("valid_tokens".to_string(), "token-1,token-2".to_string()),
+            ("bypass_paths".to_string(), "/health".to_string()),
+        ]
+        .into(),
+        ..Default::default()
+    };
+    Conformance::new()
+        .config(config)
+        .request(request("/tools", &[("Authorization", "Bearer token-1")]))
+        .request(request("/tools", &[("Authorization", "Bearer wrong")]))
+        .request(request("/health", &[]))
+        .run(&auth_plugin::AuthPlugin::new())
+        .await
+        .assert_ok();
+}
+
+#[tokio::test]
+async fn rate_limit_plugin_conforms() {
+    let config = PluginConfig {
+        custom_config: [
+            ("max_requests".to_string(), "2".to_string()),
+            ("window_seconds".to_string(), "60".to_string()),
+        ]
+        .into(),
+        ..Default::default()
+    };
+    let mut conformance = Conformance::new().config(config);
+    // Enough calls from one client to exhaust its bucket.
+    for _ in 0..12 {
+        conformance = conformance.request(request("/tools", &[]));
+    }
+    conformance
+        .run(&rate_limit_plugin::RateLimitPlugin::new())
+        .await
+        .assert_ok();
+}
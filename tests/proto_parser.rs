@@ -0,0 +1,131 @@
+//! Checks the protobuf-parse descriptor sets used by `build.rs` against `protoc`.
+//!
+//! The comparison needs `protoc` on the `PATH` (or in `PROTOC`), so it is ignored by
+//! default; CI installs protoc and runs it with `--include-ignored`.
+
+#[path = "../build/descriptor.rs"]
+mod descriptor;
+
+use prost::Message;
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Protocol versions whose vendored proto is compared.
+const VERSIONS: &[&str] = &["v1"];
+
+/// Returns the `protoc` to compare against.
+fn protoc() -> PathBuf {
+    let protoc = std::env::var_os("PROTOC")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("protoc"));
+    let available = Command::new(&protoc)
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success());
+    assert!(available, "{} is not available", protoc.display());
+    protoc
+}
+
+/// Compiles `plugin.proto` in `include_dir` with `protoc`, as prost-build invokes it.
+fn compile_with_protoc(protoc: &Path, include_dir: &Path, version: &str) -> FileDescriptorSet {
+    let out = std::env::temp_dir().join(format!(
+        "mcpd-protoc-{}-{}.bin",
+        version,
+        std::process::id()
+    ));
+    let status = Command::new(protoc)
+        .arg("--include_imports")
+        .arg("--include_source_info")
+        .arg("-I")
+        .arg(include_dir)
+        .arg("-o")
+        .arg(&out)
+        .arg("plugin.proto")
+        .status()
+        .expect("failed to run protoc");
+    assert!(
+        status.success(),
+        "protoc failed on {}",
+        include_dir.display()
+    );
+
+    let encoded = std::fs::read(&out).unwrap();
+    let _ = std::fs::remove_file(&out);
+    FileDescriptorSet::decode(encoded.as_slice()).unwrap()
+}
+
+/// Returns `file` without source info, which protobuf-parse does not record.
+fn without_source_info(file: &FileDescriptorProto) -> FileDescriptorProto {
+    FileDescriptorProto {
+        source_code_info: None,
+        ..file.clone()
+    }
+}
+
+fn include_dir(version: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("proto")
+        .join(version)
+}
+
+fn names(set: &FileDescriptorSet) -> Vec<String> {
+    set.file
+        .iter()
+        .map(|file| file.name().to_string())
+        .collect()
+}
+
+#[test]
+fn parses_vendored_protos() {
+    for version in VERSIONS {
+        let set = descriptor::parse("plugin.proto", &include_dir(version)).unwrap();
+        assert_eq!(
+            names(&set),
+            ["google/protobuf/empty.proto", "plugin.proto"],
+            "{}",
+            version
+        );
+        let plugin = &set.file[1];
+        assert_eq!(
+            plugin.package(),
+            format!("mozilla.mcpd.plugins.{}", version)
+        );
+        assert_eq!(plugin.service[0].name(), "Plugin");
+    }
+}
+
+#[test]
+#[ignore = "needs protoc; CI runs it with --include-ignored"]
+fn parser_matches_protoc() {
+    let protoc = protoc();
+
+    for version in VERSIONS {
+        let include_dir = include_dir(version);
+        let expected = compile_with_protoc(&protoc, &include_dir, version);
+        let actual = descriptor::parse("plugin.proto", &include_dir).unwrap();
+        assert_eq!(names(&actual), names(&expected), "{}: files", version);
+
+        for (actual, expected) in actual.file.iter().zip(&expected.file) {
+            if actual.name() == "plugin.proto" {
+                assert_eq!(
+                    without_source_info(actual),
+                    without_source_info(expected),
+                    "{}: descriptor of plugin.proto",
+                    version
+                );
+            } else {
+                // Well-known imports ship with protoc, whose file options vary by release;
+                // only the types they define matter to the generated code.
+                assert_eq!(actual.package, expected.package, "{}", actual.name());
+                assert_eq!(
+                    actual.message_type,
+                    expected.message_type,
+                    "{}",
+                    actual.name()
+                );
+                assert_eq!(actual.enum_type, expected.enum_type, "{}", actual.name());
+            }
+        }
+    }
+}
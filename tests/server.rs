@@ -0,0 +1,40 @@
+//! Configuration errors reported by `ServerBuilder::serve`.
+
+use mcpd_plugins_sdk::{Plugin, PluginError, ServerBuilder};
+
+struct Idle;
+
+#[tonic::async_trait]
+impl Plugin for Idle {}
+
+fn args(extra: &[&str]) -> Option<Vec<String>> {
+    let args = ["plugin", "--network", "tcp", "--address", "127.0.0.1:0"];
+    Some(
+        args.iter()
+            .chain(extra)
+            .map(|arg| arg.to_string())
+            .collect(),
+    )
+}
+
+#[tokio::test]
+async fn rejects_a_zero_in_flight_limit() {
+    let result = ServerBuilder::new(Idle)
+        .max_in_flight(0)
+        .serve(args(&[]))
+        .await;
+    assert!(
+        matches!(&result, Err(PluginError::Configuration(message)) if message.contains("max-in-flight")),
+        "{:?}",
+        result
+    );
+
+    let result = ServerBuilder::new(Idle)
+        .serve(args(&["--max-in-flight", "0"]))
+        .await;
+    assert!(
+        matches!(result, Err(PluginError::Configuration(_))),
+        "{:?}",
+        result
+    );
+}
@@ -0,0 +1,135 @@
+//! Deadlines and the timeout policy, through the adapter and over a real server.
+
+use mcpd_plugins_sdk::proto::plugin_server::Plugin as PluginService;
+use mcpd_plugins_sdk::{
+    serve, Capabilities, HttpRequest, HttpResponse, Plugin, PluginAdapter, TimeoutPolicy,
+    FLOW_REQUEST, FLOW_RESPONSE,
+};
+use std::time::{Duration, Instant};
+use tonic::client::Grpc;
+use tonic::codec::ProstCodec;
+use tonic::transport::Endpoint;
+use tonic::{Code, Request, Response, Status};
+
+/// Plugin whose handlers take longer than any deadline in these tests.
+struct Slow;
+
+#[tonic::async_trait]
+impl Plugin for Slow {
+    async fn get_capabilities(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<Capabilities>, Status> {
+        Ok(Response::new(Capabilities {
+            flows: vec![FLOW_REQUEST as i32, FLOW_RESPONSE as i32],
+        }))
+    }
+
+    async fn handle_request(
+        &self,
+        _request: Request<HttpRequest>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        Ok(Response::new(HttpResponse::default()))
+    }
+
+    async fn handle_response(
+        &self,
+        _response: Request<HttpResponse>,
+    ) -> Result<Response<HttpResponse>, Status> {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        Ok(Response::new(HttpResponse::default()))
+    }
+}
+
+fn http_response() -> HttpResponse {
+    HttpResponse {
+        r#continue: true,
+        status_code: 201,
+        headers: [("X-Upstream".to_string(), "yes".to_string())].into(),
+        body: "upstream".into(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn fail_open_continues_unchanged() {
+    let adapter = PluginAdapter::new(Slow)
+        .rpc_timeout(Duration::from_millis(20))
+        .timeout_policy(TimeoutPolicy::FailOpen);
+
+    let request = adapter
+        .handle_request(Request::new(HttpRequest::default()))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(request.r#continue);
+    assert_eq!(request.modified_request, None);
+
+    let response = adapter
+        .handle_response(Request::new(http_response()))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response, http_response());
+
+    assert_eq!(adapter.stats().timeouts_fail_open(), 2);
+    assert_eq!(adapter.stats().timeouts_fail_closed(), 0);
+}
+
+#[tokio::test]
+async fn fail_closed_returns_deadline_exceeded() {
+    let adapter = PluginAdapter::new(Slow).rpc_timeout(Duration::from_millis(20));
+
+    let status = adapter
+        .handle_request(Request::new(HttpRequest::default()))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+    assert_eq!(adapter.stats().timeouts_fail_closed(), 1);
+}
+
+/// The host's `grpc-timeout` is also enforced by tonic around the whole call; the policy
+/// must still apply when that deadline is the binding one.
+#[tokio::test]
+async fn fail_open_applies_to_the_hosts_deadline() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .unwrap()
+        .port();
+    let address = format!("127.0.0.1:{}", port);
+    let args = [
+        "plugin",
+        "--network",
+        "tcp",
+        "--address",
+        &address,
+        "--timeout-policy",
+        "fail-open",
+    ];
+    tokio::spawn(serve(Slow, Some(args.map(String::from).to_vec())));
+
+    let endpoint = Endpoint::from_shared(format!("http://{}", address)).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let channel = loop {
+        match endpoint.connect().await {
+            Ok(channel) => break channel,
+            Err(err) if Instant::now() > deadline => panic!("plugin did not start: {}", err),
+            Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+        }
+    };
+
+    let mut client = Grpc::new(channel);
+    client.ready().await.unwrap();
+    let mut request = Request::new(http_response());
+    request.set_timeout(Duration::from_millis(200));
+    let response: Response<HttpResponse> = client
+        .unary(
+            request,
+            http::uri::PathAndQuery::from_static("/mozilla.mcpd.plugins.v1.Plugin/HandleResponse"),
+            ProstCodec::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.into_inner(), http_response());
+}